@@ -0,0 +1,50 @@
+use plotters::prelude::*;
+use strum::IntoEnumIterator;
+use crate::neuron::izhikevich::IzhikevichParams;
+
+/// Draws the 20 panels of Izhikevich's "which model" figure into one image,
+/// each with its membrane potential above the injected current.
+pub fn render(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let panels: Vec<(char, IzhikevichParams)> = IzhikevichParams::iter()
+        .filter_map(|params| params.protocol().panel.map(|panel| (panel, params)))
+        .collect();
+
+    let root = BitMapBackend::new(path, (5 * 320, 4 * 240)).into_drawing_area();
+    root.fill(&WHITE)?;
+    for (area, &(panel, params)) in root.split_evenly((4, 5)).iter().zip(&panels) {
+        let protocol = params.protocol();
+        let trace = protocol.run(params.constants());
+        let end = protocol.duration.millis() as f32;
+        let (upper, lower) = area.split_vertically(area.dim_in_pixel().1 * 3 / 4);
+
+        let mut potential = ChartBuilder::on(&upper)
+            .caption(format!("({}) {}", panel, format!("{:?}", params).replace('_', " ")), ("sans-serif", 16))
+            .margin(5)
+            .y_label_area_size(30)
+            .build_cartesian_2d(0f32..end, -90f32..40f32)?;
+        potential.configure_mesh()
+            .disable_mesh()
+            .disable_x_axis()
+            .y_labels(3)
+            .draw()?;
+        potential.draw_series(LineSeries::new(
+            trace.time.iter().zip(&trace.v).map(|(t, v)| (t.millis() as f32, v.millivolts() as f32)),
+            &RED,
+        ))?;
+
+        let currents: Vec<f32> = trace.current.iter().map(|i| i.picoamps() as f32).collect();
+        let low = currents.iter().copied().fold(0f32, f32::min);
+        let high = currents.iter().copied().fold(0f32, f32::max);
+        let pad = 0.1 * (high - low).max(1.0);
+        let mut current = ChartBuilder::on(&lower)
+            .margin(5)
+            .y_label_area_size(30)
+            .build_cartesian_2d(0f32..end, (low - pad)..(high + pad))?;
+        current.draw_series(LineSeries::new(
+            trace.time.iter().zip(&currents).map(|(t, &i)| (t.millis() as f32, i)),
+            &BLUE,
+        ))?;
+    }
+    root.present()?;
+    Ok(())
+}
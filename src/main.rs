@@ -1,45 +1,55 @@
+mod gallery;
 mod neuron;
 
-use std::cell::RefCell;
-use std::rc::Rc;
 use plotters::chart::DualCoordChartContext;
 use plotters::coord::types::{RangedCoordf32, RangedCoordi32};
-use crate::neuron::engine::{NeuronEngine, GaussianSG, DCSG, SingleSpike};
+use crate::neuron::device::Device;
+use crate::neuron::engine::{NeuronEngine, PhasePlane, DCSG, SingleSpike};
+use crate::neuron::integrator::Integrator;
 use crate::neuron::izhikevich::*;
 
 use plotters::prelude::*;
-use typed_arena::Arena;
-use crate::neuron::network::{Listeners, Node, Synapse};
+use crate::neuron::network::Network;
+use crate::neuron::synapse::Synapse;
+use crate::neuron::units::{Current, Time};
 
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let rand = Box::new(GaussianSG::new(0.05));
-    let dc = Box::new(DCSG::new(10.0e0));
-    let single = Box::new(SingleSpike::new(14.0e0,100.0,10.0));
-    let sg = dc;
+    // `gallery [file]` draws every preset under its figure protocol instead
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("gallery") {
+        return gallery::render(args.get(2).map_or("gallery.png", String::as_str));
+    }
+
+    let dc = Box::new(DCSG::new(Current::from_picoamps(10.0)));
+    let single = Box::new(SingleSpike::new(Current::from_picoamps(14.0),100.0,Time::from_millis(10.0)));
     let params = IzhikevichParams::spike_freq_adapt;
     let params_name = format!("Izhikevich {:?}", params);
-    //let model: &mut Izhikevich = &mut Izhikevich::new(sg, params);
-    let arena : Arena<Node<Izhikevich>> = Arena::new();
-    let mut n1 = Rc::new(RefCell::new(Node::new(Izhikevich::new(sg,
-                                           IzhikevichParams::phasic_spiking), &arena)));
-    let mut n2 = Rc::new(RefCell::new(Node::new(Izhikevich::new(Box::new(DCSG::new(0.0)),
-                                           IzhikevichParams::intrinsically_bursting), &arena)));
-    let mut n3 = Rc::new(RefCell::new(Node::new(Izhikevich::new(Box::new(DCSG::new(0.0)),
-                                                                IzhikevichParams::tonic_spiking), &arena)));
-
-
-    n1.borrow_mut().listeners.borrow_mut().add(Box::new(|| {
-        println!("fired n1");
+    //let model: &mut Izhikevich = &mut Izhikevich::new(params);
+    let mut network = Network::new();
+    let n1 = network.add_neuron(Izhikevich::new(IzhikevichParams::phasic_spiking));
+    let n2 = network.add_neuron(Izhikevich::new(IzhikevichParams::intrinsically_bursting));
+    let n3 = network.add_neuron(Izhikevich::new(IzhikevichParams::tonic_spiking));
+
+    let stimulus = Device::new(dc);
+    network.neuron_mut(n1).unwrap().attach(&stimulus);
+
+
+    // one solver for the whole simulation; forward Euler misses spikes at this dt
+    let integrator = Integrator::HalfStep;
+    for n in [n1, n2, n3] {
+        network.neuron_mut(n).unwrap().engine.set_integrator(integrator);
+    }
+
+    network.neuron(n1).unwrap().listeners.borrow_mut().add(Box::new(|t| {
+        println!("fired n1 at {:.3} ms", t.millis());
     }));
 
-    //n1.borrow_mut().add_downstream(n2.clone());
-    let syn = Synapse::new(n2.clone(), 30.0, 3.0);
-    n1.borrow_mut().add_downstream(syn);
-    //n2.borrow_mut().add_downstream(n3.clone());
+    network.connect(n1, n2, Synapse::new(Current::from_picoamps(30.0), Time::from_millis(3.0)));
+    //network.connect(n2, n3, ...);
 
-    n2.borrow_mut().listeners.borrow_mut().add(Box::new(|| {
-        println!("fired n2");
+    network.neuron(n2).unwrap().listeners.borrow_mut().add(Box::new(|t| {
+        println!("fired n2 at {:.3} ms", t.millis());
     }));
 
 
@@ -62,7 +72,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };*/
 
     let len = 4000;
-    let time_step : f64 = 0.1;
+    let time_step = Time::from_millis(0.1);
 
     let root = BitMapBackend::new("0.png", (2*640, 480)).into_drawing_area();
     root.fill(&WHITE)?;
@@ -106,8 +116,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 
 
-    config_chart(&mut left_chart, time_step);
-    config_chart(&mut right_chart, time_step);
+    config_chart(&mut left_chart, time_step.millis());
+    config_chart(&mut right_chart, time_step.millis());
 
     let mut n1_data: Vec<(f64,f64)> = Vec::new();
     let mut n2_data: Vec<(f64,f64)> = Vec::new();
@@ -119,11 +129,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         //let i = model.step(Rc::new(RefCell::new(listeners)),time_step);
 
         //println!("potential {}", model.v);
-        let i = n1.borrow_mut().step(time_step);
-        n1_data.push((n1.borrow().get_potential(), i));
-        phase_n1.push((n1.borrow().get_potential(), n1.borrow().engine.borrow().u));
-        let i = n2.borrow_mut().step(time_step);
-        n2_data.push((n2.borrow().get_potential(), i));
+        network.step(time_step);
+        let (first, second) = (network.neuron(n1).unwrap(), network.neuron(n2).unwrap());
+        n1_data.push((first.get_potential().millivolts(), first.current.picoamps()));
+        phase_n1.push(first.engine.phase());
+        n2_data.push((second.get_potential().millivolts(), second.current.picoamps()));
     }
 
 
@@ -134,7 +144,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .draw_series(LineSeries::new(
             (0..=len).map(|x| {
                 //println!("potential {}", model.membrane_potential);
-                ((phase_n1[x as usize].0 *1.0e3) as f32, (phase_n1[x as usize].1 *1.0e0) as f32)
+                (phase_n1[x as usize].0 as f32, phase_n1[x as usize].1 as f32)
             }),
             &RED,
         ))?
@@ -193,7 +203,7 @@ fn plot_data(chart: &mut DualCoordChartContext<BitMapBackend, Cartesian2d<Ranged
         .draw_series(LineSeries::new(
             (0..=len).map(|x| {
                 //println!("potential {}", model.membrane_potential);
-                (x, (data[x as usize].0 * 1e3) as f32)
+                (x, (data[x as usize].0) as f32)
             }),
             &RED,
         ))?
@@ -0,0 +1,218 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use strum_macros::EnumString;
+use crate::neuron::engine::NeuronEngine;
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::{Ports, Receptor};
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
+
+/// Parameters of the adaptive exponential integrate-and-fire model.
+///
+/// Units: pF, nS, mV, ms and pA.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct AdExConstants {
+    pub c: f64,
+    pub g_l: f64,
+    pub e_l: f64,
+    pub v_t: f64,
+    pub delta_t: f64,
+    pub a: f64,
+    pub b: f64,
+    pub tau_w: f64,
+    pub v_r: f64,
+    pub v_peak: f64,
+}
+
+/// Firing regimes from Naud, Marcille, Clopath & Gerstner (2008), table 1.
+/// The step current that produces each pattern is given next to the variant.
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString)]
+pub enum AdExParams {
+    tonic_spiking,            // 500 pA
+    adaptation,               // 500 pA
+    initial_burst,            // 400 pA
+    regular_bursting,         // 210 pA
+    delayed_accelerating,     // 300 pA
+    delayed_regular_bursting, // 110 pA
+    transient_spiking,        // 180 pA
+    irregular_spiking,        // 160 pA
+}
+use AdExParams::*;
+
+impl AdExParams {
+    pub fn constants(&self) -> AdExConstants {
+        let (c, g_l, e_l, a, tau_w, b, v_r) = match self {
+            tonic_spiking => (200.0, 10.0, -70.0, 2.0, 30.0, 0.0, -58.0),
+            adaptation => (200.0, 12.0, -70.0, 2.0, 300.0, 60.0, -58.0),
+            initial_burst => (130.0, 18.0, -58.0, 4.0, 150.0, 120.0, -50.0),
+            regular_bursting => (200.0, 10.0, -58.0, 2.0, 120.0, 100.0, -46.0),
+            delayed_accelerating => (200.0, 12.0, -70.0, -10.0, 300.0, 0.0, -58.0),
+            delayed_regular_bursting => (100.0, 10.0, -65.0, -10.0, 90.0, 30.0, -47.0),
+            transient_spiking => (100.0, 10.0, -65.0, 10.0, 90.0, 100.0, -47.0),
+            irregular_spiking => (100.0, 12.0, -60.0, -11.0, 130.0, 30.0, -48.0),
+        };
+        AdExConstants {
+            c,
+            g_l,
+            e_l,
+            v_t: -50.0,
+            delta_t: 2.0,
+            a,
+            b,
+            tau_w,
+            v_r,
+            v_peak: 0.0,
+        }
+    }
+}
+
+/// Brette & Gerstner (2005) adaptive exponential integrate-and-fire neuron.
+pub struct AdEx {
+    pub v: f64,
+    pub w: f64,
+    constants: AdExConstants,
+    input_current: Current,
+    ports: Ports,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl AdEx {
+    pub fn new(params: AdExParams) -> Self {
+        Self::with_constants(params.constants())
+    }
+
+    pub fn with_constants(constants: AdExConstants) -> Self {
+        AdEx {
+            v: constants.e_l,
+            w: 0.0,
+            constants,
+            input_current: Current::ZERO,
+            ports: Ports::default(),
+            integrator: Integrator::default(),
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        }
+    }
+
+    pub fn constants(&self) -> &AdExConstants {
+        &self.constants
+    }
+
+    fn derivatives(&self, state: &[f64; 2], i: f64) -> [f64; 2] {
+        let p = &self.constants;
+        let (v, w) = (state[0], state[1]);
+        // the exponential is capped at v_peak so a large step cannot overflow it
+        let spike = p.g_l * p.delta_t * ((v.min(p.v_peak) - p.v_t) / p.delta_t).exp();
+        let dv = (-p.g_l * (v - p.e_l) + spike - w + i) / p.c;
+        let dw = (p.a * (v - p.e_l) - w) / p.tau_w;
+        [dv, dw]
+    }
+}
+
+impl NeuronEngine for AdEx {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.picoamps(), dt.millis());
+        let start = [self.v, self.w];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        let (v, synaptic) = self.ports.relax(Voltage::from_millivolts(state[0]), Time::from_millis(dt), Capacitance::from_picofarads(self.constants.c));
+        state[0] = v.millivolts();
+        [self.v, self.w] = state;
+        let p = self.constants;
+
+        let mut spike = None;
+        if self.v >= p.v_peak {
+            let offset = Time::from_millis(self.interpolation.crossing(p.v_peak, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+            self.v = p.v_r;
+            self.w += p.b;
+        }
+        (spike, current + synaptic)
+    }
+
+    fn reset(&mut self) {
+        self.v = self.constants.e_l;
+        self.w = 0.0;
+        self.input_current = Current::ZERO;
+        self.ports.close();
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        self.ports.open(receptor, g);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.v)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intervals(params: AdExParams, current: f64) -> Vec<f64> {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut adex = AdEx::new(params);
+        let dt = 0.01;
+        let mut times = Vec::new();
+        for n in 0..(500.0 / dt) as usize {
+            adex.receive(Current::from_picoamps(current));
+            if adex.step(listeners.clone(), Time::from_millis(dt)).0.is_some() {
+                times.push(n as f64 * dt);
+            }
+        }
+        times.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    #[test]
+    fn tonic_spiking_is_regular() {
+        let isi = intervals(tonic_spiking, 500.0);
+        assert!(isi.len() > 10);
+        let (mid, last) = (isi[isi.len() / 2], isi[isi.len() - 1]);
+        assert!((mid - last).abs() / last < 0.01, "{:?}", isi);
+    }
+
+    #[test]
+    fn adaptation_lengthens_intervals() {
+        let isi = intervals(adaptation, 500.0);
+        assert!(isi.len() > 3);
+        assert!(isi[isi.len() - 1] > 2.0 * isi[0], "{:?}", isi);
+    }
+
+    #[test]
+    fn transient_spiking_stops() {
+        let isi = intervals(transient_spiking, 180.0);
+        assert!(isi.len() < 5, "{:?}", isi);
+    }
+
+    #[test]
+    fn rests_without_input() {
+        assert!(intervals(tonic_spiking, 0.0).is_empty());
+    }
+}
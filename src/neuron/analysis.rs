@@ -0,0 +1,202 @@
+#![allow(dead_code)]
+
+use crate::neuron::units::{Time, Voltage};
+
+/// Coarse label for how a cell responds to a stimulus.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FiringPattern {
+    /// No spikes after the stimulus starts.
+    Silent,
+    /// Regular spiking for as long as the stimulus lasts.
+    TonicSpiking,
+    /// A spike or burst soon after the stimulus starts, then silence while it
+    /// is still on.
+    Phasic,
+    /// Two or more bursts of closely spaced spikes.
+    Bursting,
+    /// A burst of three or more spikes at the start of the stimulus, then
+    /// single spikes.
+    InitialBurst,
+    /// Intervals that keep lengthening to the end of the train.
+    Adapting,
+    /// A lone spike or burst well after the stimulus starts, or once it is over.
+    Latency,
+}
+
+/// Labels spike trains from their interspike intervals and bursts.
+///
+/// The tests are taken in order: silent, bursting, then a response that ends
+/// early, comes after the stimulus or is a single burst, which is phasic or
+/// latency by its delay, then adapting, an initial burst, and anything left
+/// is tonic spiking.
+#[derive(Debug, Copy, Clone)]
+pub struct Classifier {
+    /// Longest interval between spikes in the same burst.
+    pub burst_isi: Time,
+    /// Shortest delay from stimulus start to first spike counted as latency.
+    pub latency: Time,
+    /// How much longer the last interval must be than the one before for
+    /// the train to still be adapting.
+    pub adaptation: f64,
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Self {
+            burst_isi: Time::from_millis(6.0),
+            latency: Time::from_millis(10.0),
+            adaptation: 1.1,
+        }
+    }
+}
+
+impl Classifier {
+    /// Classifies the spikes fired after `start`, for a stimulus applied from
+    /// `start` to `stop`.
+    pub fn classify(&self, spikes: &[Time], start: Time, stop: Time) -> FiringPattern {
+        let spikes: Vec<Time> = spikes.iter().copied().filter(|&t| t >= start).collect();
+        if spikes.is_empty() {
+            return FiringPattern::Silent;
+        }
+        let bursts = bursts(&spikes, self.burst_isi);
+        if bursts.iter().filter(|b| b.len() > 1).count() >= 2 {
+            return FiringPattern::Bursting;
+        }
+        let (first, last) = (spikes[0], spikes[spikes.len() - 1]);
+        // spiking stopped with over half the stimulus still to go
+        if stop - last > (stop - start) * 0.5 {
+            return FiringPattern::Phasic;
+        }
+        if first >= stop || bursts.len() == 1 {
+            return if first - start > self.latency { FiringPattern::Latency } else { FiringPattern::Phasic };
+        }
+        let isi = intervals(&spikes);
+        if isi.len() >= 3 && isi.windows(2).all(|w| w[1] >= w[0])
+            && isi[isi.len() - 1] / isi[isi.len() - 2] > self.adaptation {
+            return FiringPattern::Adapting;
+        }
+        // a doublet is not yet a burst
+        if bursts[0].len() >= 3 {
+            return FiringPattern::InitialBurst;
+        }
+        FiringPattern::TonicSpiking
+    }
+
+    /// Classifies a sampled voltage trace, taking a spike as each upward
+    /// crossing of `threshold`.
+    pub fn classify_trace(&self, time: &[Time], v: &[Voltage], threshold: Voltage,
+                          start: Time, stop: Time) -> FiringPattern {
+        self.classify(&detect_spikes(time, v, threshold), start, stop)
+    }
+}
+
+/// Times at which `v` crosses `threshold` going up, placed by linear
+/// interpolation between samples.
+pub fn detect_spikes(time: &[Time], v: &[Voltage], threshold: Voltage) -> Vec<Time> {
+    let mut spikes = Vec::new();
+    for n in 1..time.len().min(v.len()) {
+        if v[n - 1] < threshold && v[n] >= threshold {
+            let frac = (threshold - v[n - 1]) / (v[n] - v[n - 1]);
+            spikes.push(time[n - 1] + (time[n] - time[n - 1]) * frac);
+        }
+    }
+    spikes
+}
+
+/// Intervals between consecutive spikes.
+pub fn intervals(spikes: &[Time]) -> Vec<Time> {
+    spikes.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Splits a spike train wherever an interval is longer than `max_isi`, so
+/// each part is a burst or a lone spike.
+pub fn bursts(spikes: &[Time], max_isi: Time) -> Vec<&[Time]> {
+    let mut bursts = Vec::new();
+    let mut from = 0;
+    for n in 1..=spikes.len() {
+        if n == spikes.len() || spikes[n] - spikes[n - 1] > max_isi {
+            bursts.push(&spikes[from..n]);
+            from = n;
+        }
+    }
+    bursts
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+    use super::*;
+    use super::FiringPattern::*;
+    use crate::neuron::izhikevich::IzhikevichParams;
+    use crate::neuron::izhikevich::IzhikevichParams::*;
+
+    fn ms(x: f64) -> Time {
+        Time::from_millis(x)
+    }
+
+    fn train(times: &[f64]) -> Vec<Time> {
+        times.iter().map(|&t| ms(t)).collect()
+    }
+
+    #[test]
+    fn finds_crossings_and_bursts() {
+        let time = train(&[0.0, 1.0, 2.0, 3.0, 4.0]);
+        let v: Vec<Voltage> = [-60.0, 20.0, -60.0, -40.0, 40.0].iter().map(|&v| Voltage::from_millivolts(v)).collect();
+        let spikes = detect_spikes(&time, &v, Voltage::ZERO);
+        assert_eq!(spikes, train(&[0.75, 3.5]));
+
+        let spikes = train(&[1.0, 3.0, 20.0, 40.0, 42.0, 44.0]);
+        let sizes: Vec<usize> = bursts(&spikes, ms(5.0)).iter().map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn labels_synthetic_trains() {
+        let c = Classifier::default();
+        let (start, stop) = (ms(10.0), ms(210.0));
+        let classify = |times: &[f64]| c.classify(&train(times), start, stop);
+        assert_eq!(classify(&[5.0]), Silent);
+        assert_eq!(classify(&[15.0, 35.0, 55.0, 75.0, 95.0, 115.0, 135.0, 155.0, 175.0, 195.0]), TonicSpiking);
+        assert_eq!(classify(&[15.0, 18.0, 80.0, 83.0, 150.0, 153.0]), Bursting);
+        assert_eq!(classify(&[14.0, 20.0]), Phasic);
+        assert_eq!(classify(&[15.0, 20.0, 30.0, 50.0, 90.0, 170.0]), Adapting);
+        assert_eq!(c.classify(&train(&[40.0]), ms(10.0), ms(15.0)), Latency);
+        assert_eq!(classify(&[12.0, 15.0, 18.0, 60.0, 110.0, 160.0, 205.0]), InitialBurst);
+    }
+
+    // spikes from the start of the stimulus, and its window
+    fn response(params: IzhikevichParams) -> (Vec<Time>, Time, Time) {
+        let protocol = params.protocol();
+        let (start, stop) = protocol.window();
+        let trace = protocol.run(params.constants());
+        let spikes = detect_spikes(&trace.time, &trace.v, Voltage::ZERO);
+        (spikes.into_iter().filter(|&t| t >= start).collect(), start, stop)
+    }
+
+    fn pattern(params: IzhikevichParams) -> FiringPattern {
+        let (spikes, start, stop) = response(params);
+        Classifier::default().classify(&spikes, start, stop)
+    }
+
+    #[test]
+    fn presets_show_their_pattern() {
+        for params in IzhikevichParams::iter() {
+            let expected = match params {
+                tonic_spiking | Class_1_excit | Class_2_excit | bistability | inh_induced_sp
+                | fast_spiking => TonicSpiking,
+                phasic_spiking | phasic_bursting | subthreshold_osc | integrator | DAP => Phasic,
+                tonic_bursting | inh_induced_brst => Bursting,
+                mixed_mode | intrinsically_bursting => InitialBurst,
+                spike_freq_adapt => Adapting,
+                // late single responses, or ones that only come after the stimulus
+                spike_latency | resonator | rebound_spike | rebound_burst | thresh_variability
+                | accomodation => Latency,
+            };
+            assert_eq!(pattern(params), expected, "{:?}", params);
+        }
+        // phasic bursting answers with a burst where phasic spiking gives one spike
+        let burst_size = |params| bursts(&response(params).0, Classifier::default().burst_isi)[0].len();
+        assert!(burst_size(phasic_bursting) >= 3, "{}", burst_size(phasic_bursting));
+        assert_eq!(burst_size(phasic_spiking), 1);
+    }
+}
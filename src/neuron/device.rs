@@ -0,0 +1,95 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::neuron::engine::SpikeGenerator;
+use crate::neuron::synapse::SynapticInput;
+use crate::neuron::units::{Current, Time};
+
+/// A stimulus source that nodes attach to and detach from at any time.
+///
+/// Clones are handles to the same source. It is stepped once per time step
+/// however many nodes read it, so every attached node sees the same input.
+/// Its waveform starts on the first step it is read.
+#[derive(Clone)]
+pub struct Device {
+    source: Rc<RefCell<Source>>,
+}
+
+struct Source {
+    generator: Box<dyn SpikeGenerator>,
+    // start of the last step generated
+    stepped: Option<Time>,
+    input: SynapticInput,
+}
+
+impl Device {
+    pub fn new(generator: Box<dyn SpikeGenerator>) -> Self {
+        Self {
+            source: Rc::new(RefCell::new(Source { generator, stepped: None, input: SynapticInput::Current(Current::ZERO) })),
+        }
+    }
+
+    /// Input over the step starting at `time`. Nodes sharing a device must
+    /// share a clock.
+    pub fn input(&self, time: Time, dt: Time) -> SynapticInput {
+        let mut source = self.source.borrow_mut();
+        if source.stepped != Some(time) {
+            source.input = source.generator.input(dt);
+            source.stepped = Some(time);
+        }
+        source.input
+    }
+
+    /// Whether both handles are to the same source.
+    pub fn same(&self, other: &Device) -> bool {
+        Rc::ptr_eq(&self.source, &other.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neuron::engine::{DCSG, RampGenerator};
+    use crate::neuron::integrate_fire::IntegrateFire;
+    use crate::neuron::network::Network;
+
+    fn ms(x: f64) -> Time {
+        Time::from_millis(x)
+    }
+
+    #[test]
+    fn shared_device_steps_once_per_step() {
+        let mut network = Network::new();
+        let a = network.add_neuron(IntegrateFire::default());
+        let b = network.add_neuron(IntegrateFire::default());
+        let ramp = Device::new(Box::new(RampGenerator::new(Current::from_nanoamps(1.0), ms(1.0))));
+        network.neuron_mut(a).unwrap().attach(&ramp);
+        network.neuron_mut(b).unwrap().attach(&ramp);
+        for n in 0..5 {
+            network.step(ms(0.1));
+            let (ia, ib) = (network.neuron(a).unwrap().current, network.neuron(b).unwrap().current);
+            assert_eq!(ia, ib);
+            assert!((ia.nanoamps() - 0.1 * n as f64).abs() < 1.0e-12, "{} {}", n, ia);
+        }
+    }
+
+    #[test]
+    fn attaches_and_detaches_during_a_run() {
+        let mut network = Network::new();
+        let id = network.add_neuron(IntegrateFire::default());
+        let dc = Device::new(Box::new(DCSG::new(Current::from_nanoamps(0.2))));
+        let step = |network: &mut Network<IntegrateFire>| {
+            network.step(ms(0.1));
+            network.neuron(id).unwrap().current
+        };
+        assert_eq!(step(&mut network), Current::ZERO);
+        network.neuron_mut(id).unwrap().attach(&dc);
+        network.neuron_mut(id).unwrap().attach(&dc);
+        step(&mut network);
+        assert_eq!(step(&mut network), Current::from_nanoamps(0.2));
+        assert!(network.neuron_mut(id).unwrap().detach(&dc));
+        assert!(!network.neuron_mut(id).unwrap().detach(&dc));
+        assert_eq!(step(&mut network), Current::ZERO);
+    }
+}
@@ -4,55 +4,113 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use rand::Rng;
 
-use crate::neuron::network;
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
 use crate::neuron::network::Listeners;
+use crate::neuron::random::Stream;
+use crate::neuron::synapse::{Receptor, SynapticInput};
+use crate::neuron::units::{Conductance, Current, Time, Voltage};
 
 pub trait NeuronEngine {
-    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: f64) -> (bool, f64);
+    /// Advance by `dt`, returning the time into the step at which the neuron
+    /// fired, if it did, and the input current.
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current);
     fn reset(&mut self);
-    fn receive(&mut self, curr: f64);
-    fn get_membrane_potential(&self) -> f64;
+    fn receive(&mut self, curr: Current);
+    fn get_membrane_potential(&self) -> Voltage;
+    fn set_integrator(&mut self, integrator: Integrator);
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation);
+    /// Adds white noise to the input current, or removes it with `None`.
+    fn set_noise(&mut self, noise: Option<WhiteNoise>);
+    /// Opens conductance `g` on a receptor port for the next step. The step
+    /// moves the membrane through it towards the receptor's reversal
+    /// potential, and counts the current it drove in the input current.
+    /// Dimensionless models have no reversal potentials and panic.
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance);
+}
+
+/// Lets a boxed engine, in particular `Box<dyn NeuronEngine>`, stand in for a
+/// concrete one, so a single network can hold different models.
+impl<E: NeuronEngine + ?Sized> NeuronEngine for Box<E> {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        (**self).step(listeners, dt)
+    }
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+    fn receive(&mut self, curr: Current) {
+        (**self).receive(curr)
+    }
+    fn get_membrane_potential(&self) -> Voltage {
+        (**self).get_membrane_potential()
+    }
+    fn set_integrator(&mut self, integrator: Integrator) {
+        (**self).set_integrator(integrator)
+    }
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        (**self).set_spike_interpolation(interpolation)
+    }
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        (**self).set_noise(noise)
+    }
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        (**self).receive_conductance(receptor, g)
+    }
+}
+
+/// Engines with two state variables worth plotting against each other,
+/// membrane variable first.
+pub trait PhasePlane {
+    fn phase(&self) -> (f64, f64);
 }
 
 pub trait SpikeGenerator {
-    fn step(&mut self, dt: f64) -> f64;
+    fn step(&mut self, dt: Time) -> Current;
+    /// Input over the next `dt`. Generators that drive a receptor rather than
+    /// inject a current give a conductance here.
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        SynapticInput::Current(self.step(dt))
+    }
 }
 
+/// Each step, with probability `rate`, a current drawn uniformly up to 1 nA.
+/// Its statistics depend on the step size; for noise use `WhiteNoise` or
+/// `stimulus::OrnsteinUhlenbeck`, and for spike train input `poisson::PoissonInput`.
 pub struct GaussianSG {
-    rate: f64
+    rate: f64,
+    rng: Stream,
 }
 
 impl GaussianSG {
-    pub fn new(rate: f64) -> Self {
+    pub fn new(rate: f64, rng: Stream) -> Self {
         GaussianSG {
-            rate
+            rate,
+            rng,
         }
     }
 }
 
 impl SpikeGenerator for GaussianSG {
-    fn step(&mut self, dt: f64) -> f64 {
-        let mut rng = rand::thread_rng();
-        if rng.gen_bool(self.rate) { rng.gen_range(0.0..1.0)*1e-9 } else {0.0}
+    fn step(&mut self, dt: Time) -> Current {
+        if self.rng.gen_bool(self.rate) { Current::from_nanoamps(self.rng.gen_range(0.0..1.0)) } else {Current::ZERO}
     }
 }
 
 pub struct DCSG {
-    mag: f64,
-    counter: f64
+    mag: Current,
+    counter: Time
 }
 impl DCSG {
-    pub fn new(mag: f64) -> Self {
+    pub fn new(mag: Current) -> Self {
         Self {
             mag: mag,
-            counter: 0.0
+            counter: Time::ZERO
         }
     }
 }
 impl SpikeGenerator for DCSG {
-    fn step(&mut self, dt: f64) -> f64 {
-        let mut i = 0.0;
-        if self.counter > 0.01 {
+    fn step(&mut self, dt: Time) -> Current {
+        let mut i = Current::ZERO;
+        if self.counter > Time::from_millis(0.01) {
             i = self.mag;
         }
         self.counter += dt;
@@ -61,26 +119,27 @@ impl SpikeGenerator for DCSG {
 }
 
 pub struct SingleSpike {
-    mag: f64,
-    pos: f64,
+    mag: Current,
+    pos: Time,
     width: f64,
-    counter: f64
+    counter: Time
 }
 
 impl SingleSpike {
-    pub fn new(mag: f64, width: f64, pos: f64) -> Self {
+    /// `width` is in steps either side of `pos`.
+    pub fn new(mag: Current, width: f64, pos: Time) -> Self {
         Self {
             mag: mag,
             pos: pos,
             width: width,
-            counter: 0.0
+            counter: Time::ZERO
         }
     }
 }
 
 impl SpikeGenerator for SingleSpike {
-    fn step(&mut self, dt: f64) -> f64 {
-        let mut i = 0.0;
+    fn step(&mut self, dt: Time) -> Current {
+        let mut i = Current::ZERO;
         if (self.counter - self.pos).abs() < self.width * dt {
             i = self.mag;
             //print!("single");
@@ -90,157 +149,27 @@ impl SpikeGenerator for SingleSpike {
     }
 }
 
-struct RampGenerator {
-    mag: f64,
-    width: f64,
-    counter: f64
+/// Rises linearly from zero to `mag` over `width`, then holds.
+pub struct RampGenerator {
+    mag: Current,
+    width: Time,
+    counter: Time
 }
 
 impl RampGenerator {
-    pub fn new(mag: f64, width: f64) -> Self {
-        Self {
-            mag: mag,
-            width: width,
-            counter: 0.0
-        }
-    }
-}
-
-
-
-
-
-
-
-
-
-
-
-
-/*
-
-pub struct IntegrateFire {
-    pub threshold: f64,
-    pub membrane_potential: f64,
-    pub reset_potential: f64,
-    pub resting_potential: f64,
-    pub membrane_capacitance: f64,
-    pub membrane_resistance: f64,
-    pub refractory_period: f64,
-    pub(crate) refractory_counter: f64,
-    pub(crate) fired: bool,
-    pub(crate) sg: Box<dyn SpikeGenerator>
-}
-
-impl IntegrateFire {
-    pub fn new(threshold: f64, resting_potential: f64, reset_potential: f64, membrane_capacitance: f64, membrane_resistance: f64, sg: Box<dyn SpikeGenerator>) -> Self {
+    pub fn new(mag: Current, width: Time) -> Self {
         Self {
-            threshold: threshold,
-            membrane_potential: resting_potential,
-            reset_potential: reset_potential,
-            resting_potential: resting_potential,
-            membrane_capacitance: membrane_capacitance,
-            membrane_resistance: membrane_resistance,
-            refractory_period: 0.0,
-            refractory_counter: 0.0,
-            fired: false,
-            sg: sg
-        }
-    }
-    pub fn default() {
-        let sg = Box::new(GaussianSG::new(0.1));
-        let model = &mut Self { //&mut Izhikevich ::new(sg);
-            membrane_potential: -70.0e-3,
-            reset_potential: -80.0e-3,
-            resting_potential: -70.0e-3,
-            membrane_capacitance: 0.2e-9,
-            membrane_resistance: 100.0e6,
-            threshold: -55.0e-3,
-            fired: false,
-            refractory_period: 2.0e-3,
-            refractory_counter: 0.0,
-            sg: sg
-        };
-    }
-}
-
-impl NeuronEngine for IntegrateFire {
-    #[allow(non_snake_case)]
-    fn step(&mut self, dt: f64) -> f64{
-        let mut I : f64 = 0.0;
-        if self.refractory_counter > 0.0 {
-            if self.membrane_potential > self.threshold {
-                self.membrane_potential = self.reset_potential;
-            } else {
-                self.membrane_potential +=  1.0 / self.membrane_resistance * (self.membrane_potential - self.reset_potential);
-            }
-            self.refractory_counter -= dt;
-        } else {
-            let incoming = self.sg.step(dt);
-            let leak = (- 1.0 / self.membrane_resistance) * (self.membrane_potential - self.resting_potential);
-            I =   incoming + leak;
-            println!("total current {}, leak current: {}", I, leak);
-            let dV = I / self.membrane_capacitance * dt;
-            self.membrane_potential += dV;
-
-            if self.membrane_potential > self.threshold {
-                self.refractory_counter = self.refractory_period;
-                self.membrane_potential = 20e-3;
-                self.fired = true;
-                //self.membrane_potential = self.reset_potential;
-                println!("fire");
-            }
-        }
-        I
-    }
-
-    fn reset(&mut self) {
-        self.membrane_potential = self.reset_potential;
-    }
-
-    fn checK_fire(&mut self, listeners: Listeners) {
-        todo!()
-    }
-
-    fn get_membrane_potential(&self) -> f64 {
-        self.membrane_potential
-    }
-}
-
-
-pub struct HHGate {
-    alpha: f64,
-    beta: f64,
-
-}
-
-pub struct HodgkinsHuxley {
-}
-
-impl HodgkinsHuxley {
-    pub fn new() -> Self {
-        HodgkinsHuxley {
-
+            mag,
+            width,
+            counter: Time::ZERO
         }
     }
 }
 
-impl NeuronEngine for HodgkinsHuxley {
-    fn step(&mut self, dt: f64) -> f64 {
-        todo!()
-    }
-
-    fn reset(&mut self) {
-        todo!()
-    }
-
-    fn check_fire(&mut self, listeners: Listeners) {
-        todo!()
-    }
-
-    fn get_membrane_potential(&self) -> f64 {
-        todo!()
+impl SpikeGenerator for RampGenerator {
+    fn step(&mut self, dt: Time) -> Current {
+        let i = if self.counter < self.width { self.mag * (self.counter / self.width) } else { self.mag };
+        self.counter += dt;
+        i
     }
 }
-
- */
\ No newline at end of file
@@ -0,0 +1,164 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::neuron::engine::{NeuronEngine, PhasePlane};
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::Receptor;
+use crate::neuron::units::{Conductance, Current, Time, Voltage};
+
+/// FitzHugh (1961) / Nagumo (1962) relaxation oscillator:
+/// `v' = v - v^3 / 3 - w + I`, `w' = epsilon * (v + a - b * w)`.
+///
+/// The model is dimensionless; time is read as ms, `v` as mV when
+/// reporting the membrane potential and injected current as nA. With no
+/// reversal potentials to relax towards it takes no receptor conductances.
+pub struct FitzHughNagumo {
+    pub v: f64,
+    pub w: f64,
+    a: f64,
+    b: f64,
+    epsilon: f64,
+    threshold: f64,
+    input_current: Current,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl FitzHughNagumo {
+    /// FitzHugh's original values a = 0.7, b = 0.8, epsilon = 0.08.
+    pub fn new() -> Self {
+        Self::with_constants(0.7, 0.8, 0.08)
+    }
+
+    pub fn with_constants(a: f64, b: f64, epsilon: f64) -> Self {
+        let mut model = FitzHughNagumo {
+            v: 0.0,
+            w: 0.0,
+            a,
+            b,
+            epsilon,
+            threshold: 1.0,
+            input_current: Current::ZERO,
+            integrator: Integrator::default(),
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        };
+        model.reset();
+        model
+    }
+
+    // the unstimulated fixed point, where the cubic and linear nullclines meet
+    fn rest(&self) -> (f64, f64) {
+        let mut v: f64 = -1.0;
+        for _ in 0..50 {
+            let f = v - v.powi(3) / 3.0 - (v + self.a) / self.b;
+            let df = 1.0 - v * v - 1.0 / self.b;
+            v -= f / df;
+        }
+        (v, (v + self.a) / self.b)
+    }
+
+    fn derivatives(&self, state: &[f64; 2], i: f64) -> [f64; 2] {
+        let (v, w) = (state[0], state[1]);
+        [v - v.powi(3) / 3.0 - w + i, self.epsilon * (v + self.a - self.b * w)]
+    }
+}
+
+impl Default for FitzHughNagumo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NeuronEngine for FitzHughNagumo {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.nanoamps(), dt.millis());
+        let start = [self.v, self.w];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        [self.v, self.w] = state;
+
+        let mut spike = None;
+        if start[0] < self.threshold && state[0] >= self.threshold {
+            let offset = Time::from_millis(self.interpolation.crossing(self.threshold, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+        }
+        (spike, current)
+    }
+
+    fn reset(&mut self) {
+        (self.v, self.w) = self.rest();
+        self.input_current = Current::ZERO;
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, _g: Conductance) {
+        panic!("the dimensionless FitzHugh-Nagumo model has no reversal potentials for {:?} to pull `v` towards", receptor);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.v)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+impl PhasePlane for FitzHughNagumo {
+    fn phase(&self) -> (f64, f64) {
+        (self.v, self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spike_count(current: f64) -> usize {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut fhn = FitzHughNagumo::new();
+        (0..20000).filter(|_| {
+            fhn.receive(Current::from_nanoamps(current));
+            fhn.step(listeners.clone(), Time::from_millis(0.05)).0.is_some()
+        }).count()
+    }
+
+    #[test]
+    fn starts_at_rest() {
+        let fhn = FitzHughNagumo::new();
+        assert!((fhn.v + 1.1994).abs() < 1.0e-3);
+        assert!((fhn.w + 0.6243).abs() < 1.0e-3);
+        assert_eq!(spike_count(0.0), 0);
+    }
+
+    #[test]
+    fn oscillates_past_hopf_point() {
+        assert!(spike_count(0.5) > 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "dimensionless")]
+    fn refuses_conductances() {
+        FitzHughNagumo::new().receive_conductance(Receptor::GabaA, Conductance::from_nanosiemens(1.0));
+    }
+}
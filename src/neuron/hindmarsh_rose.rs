@@ -0,0 +1,223 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use strum_macros::EnumString;
+use crate::neuron::engine::{NeuronEngine, PhasePlane};
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::Receptor;
+use crate::neuron::units::{Conductance, Current, Time, Voltage};
+
+/// Parameters of the Hindmarsh-Rose model
+/// `x' = y - a x^3 + b x^2 - z + I`, `y' = c - d x^2 - y`, `z' = r (s (x - x_r) - z)`.
+///
+/// `bias` is the constant `I` that selects the regime; injected current adds to it.
+/// The model is dimensionless, time is read as ms, `x` as mV and injected current as nA.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct HindmarshRoseConstants {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub r: f64,
+    pub s: f64,
+    pub x_r: f64,
+    pub bias: f64,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString)]
+pub enum HindmarshRoseParams {
+    square_wave_bursting,
+    periodic_bursting,
+    chaotic_bursting,
+    tonic_spiking,
+}
+use HindmarshRoseParams::*;
+
+impl HindmarshRoseParams {
+    pub fn constants(&self) -> HindmarshRoseConstants {
+        let (r, bias) = match self {
+            square_wave_bursting => (0.001, 2.0),
+            periodic_bursting => (0.006, 2.5),
+            chaotic_bursting => (0.005, 3.25),
+            tonic_spiking => (0.006, 4.0),
+        };
+        HindmarshRoseConstants {
+            a: 1.0,
+            b: 3.0,
+            c: 1.0,
+            d: 5.0,
+            r,
+            s: 4.0,
+            x_r: -1.6,
+            bias,
+        }
+    }
+}
+
+/// Hindmarsh & Rose (1984) three variable bursting neuron. Being
+/// dimensionless it has no reversal potentials, and takes no receptor
+/// conductances.
+pub struct HindmarshRose {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    constants: HindmarshRoseConstants,
+    threshold: f64,
+    input_current: Current,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl HindmarshRose {
+    pub fn new(params: HindmarshRoseParams) -> Self {
+        Self::with_constants(params.constants())
+    }
+
+    pub fn with_constants(constants: HindmarshRoseConstants) -> Self {
+        let mut model = HindmarshRose {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            constants,
+            threshold: 1.0,
+            input_current: Current::ZERO,
+            integrator: Integrator::default(),
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        };
+        model.reset();
+        model
+    }
+
+    pub fn constants(&self) -> &HindmarshRoseConstants {
+        &self.constants
+    }
+
+    fn derivatives(&self, state: &[f64; 3], i: f64) -> [f64; 3] {
+        let p = &self.constants;
+        let [x, y, z] = *state;
+        [
+            y - p.a * x.powi(3) + p.b * x * x - z + p.bias + i,
+            p.c - p.d * x * x - y,
+            p.r * (p.s * (x - p.x_r) - z),
+        ]
+    }
+}
+
+impl NeuronEngine for HindmarshRose {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.nanoamps(), dt.millis());
+        let start = [self.x, self.y, self.z];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        [self.x, self.y, self.z] = state;
+
+        let mut spike = None;
+        if start[0] < self.threshold && state[0] >= self.threshold {
+            let offset = Time::from_millis(self.interpolation.crossing(self.threshold, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+        }
+        (spike, current)
+    }
+
+    fn reset(&mut self) {
+        let p = &self.constants;
+        self.x = p.x_r;
+        self.y = p.c - p.d * p.x_r * p.x_r;
+        self.z = 0.0;
+        self.input_current = Current::ZERO;
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, _g: Conductance) {
+        panic!("the dimensionless Hindmarsh-Rose model has no reversal potentials for {:?} to pull `x` towards", receptor);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.x)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+/// Plots the fast variable against the slow adaptation current `z`,
+/// the projection in which bursts are easiest to read.
+impl PhasePlane for HindmarshRose {
+    fn phase(&self) -> (f64, f64) {
+        (self.x, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // interspike intervals after the initial transient
+    fn intervals(params: HindmarshRoseParams) -> Vec<f64> {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut hr = HindmarshRose::new(params);
+        let dt = 0.01;
+        let mut times = Vec::new();
+        for n in 0..(4000.0 / dt) as usize {
+            if hr.step(listeners.clone(), Time::from_millis(dt)).0.is_some() && n as f64 * dt > 1000.0 {
+                times.push(n as f64 * dt);
+            }
+        }
+        times.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    #[test]
+    fn tonic_spiking_has_no_long_pauses() {
+        let isi = intervals(tonic_spiking);
+        let (min, max) = isi.iter().fold((f64::MAX, 0.0f64), |(lo, hi), &i| (lo.min(i), hi.max(i)));
+        assert!(isi.len() > 50);
+        assert!(max < 1.5 * min, "min {} max {}", min, max);
+    }
+
+    #[test]
+    fn bursting_has_quiescent_pauses() {
+        for params in [square_wave_bursting, periodic_bursting, chaotic_bursting] {
+            let isi = intervals(params);
+            let (min, max) = isi.iter().fold((f64::MAX, 0.0f64), |(lo, hi), &i| (lo.min(i), hi.max(i)));
+            assert!(isi.len() > 10, "{:?}", params);
+            assert!(max > 3.0 * min, "{:?} min {} max {}", params, min, max);
+        }
+    }
+
+    #[test]
+    fn chaotic_bursting_does_not_repeat() {
+        let isi = intervals(chaotic_bursting);
+        for period in 1..=12 {
+            let repeats = isi.windows(period + 1).all(|w| (w[0] - w[period]).abs() < 0.1);
+            assert!(!repeats, "period {} orbit", period);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "dimensionless")]
+    fn refuses_conductances() {
+        HindmarshRose::new(tonic_spiking).receive_conductance(Receptor::Nmda, Conductance::from_nanosiemens(1.0));
+    }
+}
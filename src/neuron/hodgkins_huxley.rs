@@ -0,0 +1,257 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::neuron::engine::NeuronEngine;
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::{Ports, Receptor};
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
+
+/// A voltage dependent gating variable with first order kinetics
+/// `dx/dt = alpha(v) * (1 - x) - beta(v) * x`.
+pub struct HHGate {
+    pub value: f64,
+    alpha: fn(f64) -> f64,
+    beta: fn(f64) -> f64,
+}
+
+impl HHGate {
+    pub fn new(alpha: fn(f64) -> f64, beta: fn(f64) -> f64, v: f64) -> Self {
+        let mut gate = HHGate {
+            value: 0.0,
+            alpha,
+            beta,
+        };
+        gate.value = gate.steady_state(v);
+        gate
+    }
+
+    pub fn steady_state(&self, v: f64) -> f64 {
+        let (alpha, beta) = ((self.alpha)(v), (self.beta)(v));
+        alpha / (alpha + beta)
+    }
+
+    pub fn time_constant(&self, v: f64) -> f64 {
+        1.0 / ((self.alpha)(v) + (self.beta)(v))
+    }
+
+    /// `dx/dt` for an opening `x` at membrane potential `v`.
+    pub fn rate(&self, v: f64, x: f64) -> f64 {
+        (self.alpha)(v) * (1.0 - x) - (self.beta)(v) * x
+    }
+}
+
+// x / (1 - exp(-x / k)) with the removable singularity at x = 0
+fn vtrap(x: f64, k: f64) -> f64 {
+    if (x / k).abs() < 1.0e-6 {
+        k * (1.0 + x / (2.0 * k))
+    } else {
+        x / (1.0 - (-x / k).exp())
+    }
+}
+
+fn alpha_m(v: f64) -> f64 { 0.1 * vtrap(v + 40.0, 10.0) }
+fn beta_m(v: f64) -> f64 { 4.0 * (-(v + 65.0) / 18.0).exp() }
+fn alpha_h(v: f64) -> f64 { 0.07 * (-(v + 65.0) / 20.0).exp() }
+fn beta_h(v: f64) -> f64 { 1.0 / (1.0 + (-(v + 35.0) / 10.0).exp()) }
+fn alpha_n(v: f64) -> f64 { 0.01 * vtrap(v + 55.0, 10.0) }
+fn beta_n(v: f64) -> f64 { 0.125 * (-(v + 65.0) / 80.0).exp() }
+
+/// The squid giant axon model of Hodgkin & Huxley (1952), shifted so that
+/// rest sits at -65 mV.
+///
+/// Units: mV, ms, mS/cm^2 and uF/cm^2. Injected current is divided by `area`
+/// to get the uA/cm^2 the equations use; the default of 1e-3 cm^2 makes 1 nA
+/// a density of 1 uA/cm^2.
+pub struct HodgkinsHuxley {
+    pub v: f64,
+    /// Membrane area in cm^2.
+    pub area: f64,
+    pub m: HHGate,
+    pub h: HHGate,
+    pub n: HHGate,
+    threshold: f64,
+    capacitance: f64,
+    g_na: f64,
+    g_k: f64,
+    g_leak: f64,
+    e_na: f64,
+    e_k: f64,
+    e_leak: f64,
+    resting_potential: f64,
+    input_current: Current,
+    ports: Ports,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl HodgkinsHuxley {
+    pub fn new() -> Self {
+        let v0 = -65.0;
+        HodgkinsHuxley {
+            v: v0,
+            area: 1.0e-3,
+            m: HHGate::new(alpha_m, beta_m, v0),
+            h: HHGate::new(alpha_h, beta_h, v0),
+            n: HHGate::new(alpha_n, beta_n, v0),
+            threshold: 0.0,
+            capacitance: 1.0,
+            g_na: 120.0,
+            g_k: 36.0,
+            g_leak: 0.3,
+            e_na: 50.0,
+            e_k: -77.0,
+            e_leak: -54.387,
+            resting_potential: v0,
+            input_current: Current::ZERO,
+            ports: Ports::default(),
+            // exact for the gates at a fixed potential, and keeps them inside [0, 1]
+            integrator: Integrator::ExponentialEuler,
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        }
+    }
+
+    pub fn sodium_current(&self) -> f64 {
+        self.g_na * self.m.value.powi(3) * self.h.value * (self.v - self.e_na)
+    }
+
+    pub fn potassium_current(&self) -> f64 {
+        self.g_k * self.n.value.powi(4) * (self.v - self.e_k)
+    }
+
+    pub fn leak_current(&self) -> f64 {
+        self.g_leak * (self.v - self.e_leak)
+    }
+
+    fn derivatives(&self, state: &[f64; 4], i: f64) -> [f64; 4] {
+        let [v, m, h, n] = *state;
+        let ionic = self.g_na * m.powi(3) * h * (v - self.e_na)
+            + self.g_k * n.powi(4) * (v - self.e_k)
+            + self.g_leak * (v - self.e_leak);
+        [
+            (i - ionic) / self.capacitance,
+            self.m.rate(v, m),
+            self.h.rate(v, h),
+            self.n.rate(v, n),
+        ]
+    }
+}
+
+impl Default for HodgkinsHuxley {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NeuronEngine for HodgkinsHuxley {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.microamps() / self.area, dt.millis());
+        let start = [self.v, self.m.value, self.h.value, self.n.value];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        let (v, synaptic) = self.ports.relax(Voltage::from_millivolts(state[0]), Time::from_millis(dt), Capacitance::from_microfarads(self.capacitance * self.area));
+        state[0] = v.millivolts();
+        [self.v, self.m.value, self.h.value, self.n.value] = state;
+
+        // no reset, a spike is an upward crossing of the threshold
+        let mut spike = None;
+        if start[0] < self.threshold && state[0] >= self.threshold {
+            let offset = Time::from_millis(self.interpolation.crossing(self.threshold, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+        }
+        (spike, current + synaptic)
+    }
+
+    fn reset(&mut self) {
+        self.v = self.resting_potential;
+        self.m.value = self.m.steady_state(self.v);
+        self.h.value = self.h.steady_state(self.v);
+        self.n.value = self.n.steady_state(self.v);
+        self.input_current = Current::ZERO;
+        self.ports.close();
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        self.ports.open(receptor, g);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.v)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // returns the spike count and peak potential (mV) over `duration` ms
+    fn run(current: f64, duration: f64, dt: f64) -> (usize, f64) {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut hh = HodgkinsHuxley::new();
+        let mut spikes = 0;
+        let mut peak = f64::MIN;
+        for _ in 0..(duration / dt) as usize {
+            hh.receive(Current::from_nanoamps(current));
+            if hh.step(listeners.clone(), Time::from_millis(dt)).0.is_some() {
+                spikes += 1;
+            }
+            peak = peak.max(hh.v);
+        }
+        (spikes, peak)
+    }
+
+    #[test]
+    fn rests_at_minus_65() {
+        let (spikes, peak) = run(0.0, 100.0, 0.01);
+        assert_eq!(spikes, 0);
+        assert!((peak + 65.0).abs() < 0.1, "peak {}", peak);
+    }
+
+    #[test]
+    fn action_potential_overshoots() {
+        // published peak is roughly +40 mV, below E_Na = +50 mV
+        let (spikes, peak) = run(10.0, 50.0, 0.01);
+        assert!(spikes >= 1);
+        assert!(peak > 30.0 && peak < 50.0, "peak {}", peak);
+    }
+
+    #[test]
+    fn repetitive_firing_threshold() {
+        // sustained firing starts near 6.2 uA/cm^2
+        let (below, _) = run(5.5, 500.0, 0.01);
+        let (above, _) = run(7.0, 500.0, 0.01);
+        assert!(below <= 1, "{} spikes at 5.5 uA/cm^2", below);
+        assert!(above > 10, "{} spikes at 7.0 uA/cm^2", above);
+    }
+
+    #[test]
+    fn firing_rate_at_10ua() {
+        // roughly 68 Hz at 10 uA/cm^2
+        let (spikes, _) = run(10.0, 1000.0, 0.01);
+        assert!((60..=75).contains(&spikes), "{} spikes", spikes);
+    }
+}
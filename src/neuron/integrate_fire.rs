@@ -0,0 +1,222 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::neuron::engine::NeuronEngine;
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::{Ports, Receptor};
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
+
+/// Leaky integrate-and-fire neuron with an absolute refractory period.
+///
+/// Units: mV, ms, MOhm, nF and nA for injected current, so that
+/// `membrane_resistance * membrane_capacitance` is the membrane time constant in ms.
+pub struct IntegrateFire {
+    pub threshold: f64,
+    pub membrane_potential: f64,
+    pub reset_potential: f64,
+    pub resting_potential: f64,
+    pub membrane_capacitance: f64,
+    pub membrane_resistance: f64,
+    pub refractory_period: f64,
+    refractory_counter: f64,
+    input_current: Current,
+    ports: Ports,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl Default for IntegrateFire {
+    /// A generic cortical cell: rest at -70 mV, threshold at -55 mV, 20 ms time constant.
+    fn default() -> Self {
+        Self::new(-55.0, -70.0, -80.0, 0.2, 100.0, 2.0)
+    }
+}
+
+impl IntegrateFire {
+    pub fn new(threshold: f64, resting_potential: f64, reset_potential: f64,
+               membrane_capacitance: f64, membrane_resistance: f64, refractory_period: f64) -> Self {
+        Self {
+            threshold,
+            membrane_potential: resting_potential,
+            reset_potential,
+            resting_potential,
+            membrane_capacitance,
+            membrane_resistance,
+            refractory_period,
+            refractory_counter: 0.0,
+            input_current: Current::ZERO,
+            ports: Ports::default(),
+            // exact for the linear membrane equation
+            integrator: Integrator::ExponentialEuler,
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        }
+    }
+
+    pub fn time_constant(&self) -> f64 {
+        self.membrane_resistance * self.membrane_capacitance
+    }
+
+    pub fn is_refractory(&self) -> bool {
+        self.refractory_counter > 0.0
+    }
+
+    fn derivatives(&self, state: &[f64; 1], i: f64) -> [f64; 1] {
+        let v = state[0];
+        [(self.resting_potential - v + self.membrane_resistance * i) / self.time_constant()]
+    }
+}
+
+impl NeuronEngine for IntegrateFire {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.nanoamps(), dt.millis());
+        if self.is_refractory() {
+            self.refractory_counter -= dt;
+            self.ports.close();
+            return (None, current);
+        }
+
+        let start = [self.membrane_potential];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        let (v, synaptic) = self.ports.relax(Voltage::from_millivolts(state[0]), Time::from_millis(dt), Capacitance::from_nanofarads(self.membrane_capacitance));
+        state[0] = v.millivolts();
+        self.membrane_potential = state[0];
+
+        let mut spike = None;
+        if self.membrane_potential >= self.threshold {
+            let offset = Time::from_millis(self.interpolation.crossing(self.threshold, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+            self.membrane_potential = self.reset_potential;
+            // the refractory period runs from the spike, not from the end of the step
+            self.refractory_counter = self.refractory_period - (dt - offset.millis());
+        }
+        (spike, current + synaptic)
+    }
+
+    fn reset(&mut self) {
+        self.membrane_potential = self.resting_potential;
+        self.refractory_counter = 0.0;
+        self.input_current = Current::ZERO;
+        self.ports.close();
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        self.ports.open(receptor, g);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.membrane_potential)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use super::*;
+
+    fn spike_times(lif: &mut IntegrateFire, current: Current, duration: f64, dt: f64) -> Vec<f64> {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut times = Vec::new();
+        for n in 0..(duration / dt) as usize {
+            lif.receive(current);
+            if let Some(offset) = lif.step(listeners.clone(), Time::from_millis(dt)).0 {
+                times.push(n as f64 * dt + offset.millis());
+            }
+        }
+        times
+    }
+
+    #[test]
+    fn subthreshold_input_settles_below_threshold() {
+        // 0.1 nA * 100 MOhm = 10 mV above rest
+        let mut lif = IntegrateFire::default();
+        assert!(spike_times(&mut lif, Current::from_nanoamps(0.1), 500.0, 0.1).is_empty());
+        assert!((lif.membrane_potential + 60.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn interspike_interval_matches_analytic() {
+        let mut lif = IntegrateFire::default();
+        let times = spike_times(&mut lif, Current::from_nanoamps(0.3), 500.0, 0.01);
+        let v_inf: f64 = -70.0 + 100.0 * 0.3;
+        let expected = 2.0 + 20.0 * ((v_inf + 80.0) / (v_inf + 55.0)).ln();
+        let isi = times[times.len() - 1] - times[times.len() - 2];
+        assert!((isi - expected).abs() < 0.01, "isi {} expected {}", isi, expected);
+    }
+
+    #[test]
+    fn spike_time_is_interpolated_inside_the_step() {
+        // first crossing of v(t) = -40 - 30 exp(-t / 20) through -55
+        let expected = 20.0 * 2.0f64.ln();
+        for (interpolation, tolerance) in [(SpikeInterpolation::EndOfStep, 0.5),
+                                           (SpikeInterpolation::Linear, 0.01),
+                                           (SpikeInterpolation::CubicHermite, 1.0e-4)] {
+            let mut lif = IntegrateFire::default();
+            lif.set_spike_interpolation(interpolation);
+            let listeners = Rc::new(RefCell::new(Listeners::new()));
+            let mut n = 0;
+            let time = loop {
+                lif.receive(Current::from_nanoamps(0.3));
+                if let Some(offset) = lif.step(listeners.clone(), Time::from_millis(0.5)).0 {
+                    break n as f64 * 0.5 + offset.millis();
+                }
+                n += 1;
+            };
+            assert!((time - expected).abs() < tolerance, "{:?} {} {}", interpolation, time, expected);
+        }
+    }
+
+    #[test]
+    fn refractory_period_blocks_input() {
+        let mut lif = IntegrateFire::default();
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        lif.receive(Current::from_nanoamps(100.0));
+        assert!(lif.step(listeners.clone(), Time::from_millis(0.1)).0.is_some());
+        lif.receive(Current::from_nanoamps(100.0));
+        assert!(lif.step(listeners, Time::from_millis(0.1)).0.is_none());
+        assert_eq!(lif.membrane_potential, -80.0);
+    }
+
+    #[test]
+    fn informs_listeners() {
+        let count = Rc::new(Cell::new(0));
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let c = count.clone();
+        listeners.borrow_mut().add(Box::new(move |_| c.set(c.get() + 1)));
+        let mut lif = IntegrateFire::default();
+        let mut fired = 0;
+        for _ in 0..1000 {
+            lif.receive(Current::from_nanoamps(0.3));
+            if lif.step(listeners.clone(), Time::from_millis(0.1)).0.is_some() {
+                fired += 1;
+            }
+        }
+        assert!(fired > 0);
+        assert_eq!(count.get(), fired);
+    }
+}
@@ -0,0 +1,317 @@
+#![allow(dead_code)]
+
+use rand_distr::{Distribution, StandardNormal};
+use crate::neuron::random::Stream;
+use crate::neuron::units::{Current, Time};
+
+/// Numerical scheme used by an engine to advance its state variables over one step.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum Integrator {
+    #[default]
+    ForwardEuler,
+    /// Izhikevich (2003): the first variable takes two half steps with the
+    /// others held fixed, then the rest take one full step from the updated state.
+    HalfStep,
+    /// Forward Euler taken one variable at a time, each using those already
+    /// updated, as in the scripts behind Izhikevich's figures.
+    SemiImplicitEuler,
+    RungeKutta4,
+    /// Treats each variable as `x' = a - b x` with `b` taken from the diagonal of
+    /// the Jacobian. Exact for linear equations such as gating variables.
+    ExponentialEuler,
+    /// Dormand-Prince 5(4) with adaptive sub-steps inside `dt`, keeping the
+    /// estimated local error below `tolerance * (1 + |x|)` for every variable.
+    RungeKutta45 { tolerance: f64 },
+}
+
+impl Integrator {
+    /// Advance `state` by `dt` under `x' = f(x)`.
+    pub fn step<const N: usize, F>(&self, state: &mut [f64; N], dt: f64, f: F)
+        where F: Fn(&[f64; N]) -> [f64; N] {
+        match *self {
+            Integrator::ForwardEuler => {
+                let d = f(state);
+                for (x, d) in state.iter_mut().zip(d) {
+                    *x += dt * d;
+                }
+            }
+            Integrator::HalfStep => {
+                for _ in 0..2 {
+                    let d = f(state);
+                    state[0] += 0.5 * dt * d[0];
+                }
+                let d = f(state);
+                for (x, d) in state.iter_mut().zip(d).skip(1) {
+                    *x += dt * d;
+                }
+            }
+            Integrator::SemiImplicitEuler => {
+                for i in 0..N {
+                    state[i] += dt * f(state)[i];
+                }
+            }
+            Integrator::RungeKutta4 => {
+                let k1 = f(state);
+                let k2 = f(&offset(state, &[(0.5 * dt, &k1)]));
+                let k3 = f(&offset(state, &[(0.5 * dt, &k2)]));
+                let k4 = f(&offset(state, &[(dt, &k3)]));
+                for i in 0..N {
+                    state[i] += dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+                }
+            }
+            Integrator::ExponentialEuler => {
+                let d = f(state);
+                let mut next = *state;
+                for i in 0..N {
+                    let h = 1.0e-6 * state[i].abs().max(1.0);
+                    let mut probe = *state;
+                    probe[i] += h;
+                    let b = -(f(&probe)[i] - d[i]) / h;
+                    next[i] += if (b * dt).abs() < 1.0e-9 {
+                        d[i] * dt
+                    } else {
+                        d[i] * (1.0 - (-b * dt).exp()) / b
+                    };
+                }
+                *state = next;
+            }
+            Integrator::RungeKutta45 { tolerance } => {
+                dormand_prince(state, dt, tolerance, &f);
+            }
+        }
+    }
+}
+
+/// How an engine places a threshold crossing inside the step in which it happened.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum SpikeInterpolation {
+    /// Report the spike at the end of the step, as if it happened on the grid.
+    EndOfStep,
+    /// Straight line between the potentials at both ends of the step.
+    #[default]
+    Linear,
+    /// Cubic through both potentials and their derivatives.
+    CubicHermite,
+}
+
+impl SpikeInterpolation {
+    /// Time after the start of a step of length `dt` at which the potential
+    /// crossed `threshold` going from `v0` (slope `dv0`) to `v1` (slope `dv1`).
+    pub fn crossing(&self, threshold: f64, dt: f64, v0: f64, dv0: f64, v1: f64, dv1: f64) -> f64 {
+        let linear = ((threshold - v0) / (v1 - v0)).clamp(0.0, 1.0);
+        match self {
+            SpikeInterpolation::EndOfStep => dt,
+            SpikeInterpolation::Linear => linear * dt,
+            SpikeInterpolation::CubicHermite => {
+                let (m0, m1) = (dv0 * dt, dv1 * dt);
+                let p = |s: f64| {
+                    let (s2, s3) = (s * s, s * s * s);
+                    (2.0 * s3 - 3.0 * s2 + 1.0) * v0 + (s3 - 2.0 * s2 + s) * m0
+                        + (-2.0 * s3 + 3.0 * s2) * v1 + (s3 - s2) * m1 - threshold
+                };
+                // bisection for the first crossing, the cubic may wiggle when the slopes are steep
+                let (mut lo, mut hi) = (0.0, 1.0);
+                if p(lo) > 0.0 || p(hi) < 0.0 {
+                    return linear * dt;
+                }
+                for _ in 0..50 {
+                    let mid = 0.5 * (lo + hi);
+                    if p(mid) < 0.0 { lo = mid } else { hi = mid }
+                }
+                0.5 * (lo + hi) * dt
+            }
+        }
+    }
+}
+
+// state + sum(h * k) over the given stages
+fn offset<const N: usize>(state: &[f64; N], stages: &[(f64, &[f64; N])]) -> [f64; N] {
+    let mut out = *state;
+    for (h, k) in stages {
+        for (x, k) in out.iter_mut().zip(k.iter()) {
+            *x += h * k;
+        }
+    }
+    out
+}
+
+fn dormand_prince<const N: usize, F>(state: &mut [f64; N], dt: f64, tolerance: f64, f: &F)
+    where F: Fn(&[f64; N]) -> [f64; N] {
+    let mut t = 0.0;
+    let mut h = dt;
+    while t < dt {
+        h = h.min(dt - t);
+        let k1 = f(state);
+        let k2 = f(&offset(state, &[(h / 5.0, &k1)]));
+        let k3 = f(&offset(state, &[(h * 3.0 / 40.0, &k1), (h * 9.0 / 40.0, &k2)]));
+        let k4 = f(&offset(state, &[(h * 44.0 / 45.0, &k1), (h * -56.0 / 15.0, &k2),
+            (h * 32.0 / 9.0, &k3)]));
+        let k5 = f(&offset(state, &[(h * 19372.0 / 6561.0, &k1), (h * -25360.0 / 2187.0, &k2),
+            (h * 64448.0 / 6561.0, &k3), (h * -212.0 / 729.0, &k4)]));
+        let k6 = f(&offset(state, &[(h * 9017.0 / 3168.0, &k1), (h * -355.0 / 33.0, &k2),
+            (h * 46732.0 / 5247.0, &k3), (h * 49.0 / 176.0, &k4), (h * -5103.0 / 18656.0, &k5)]));
+        let next = offset(state, &[(h * 35.0 / 384.0, &k1), (h * 500.0 / 1113.0, &k3),
+            (h * 125.0 / 192.0, &k4), (h * -2187.0 / 6784.0, &k5), (h * 11.0 / 84.0, &k6)]);
+        let k7 = f(&next);
+
+        // difference between the 5th and embedded 4th order solutions
+        let mut error: f64 = 0.0;
+        for i in 0..N {
+            let e = h * (71.0 / 57600.0 * k1[i] - 71.0 / 16695.0 * k3[i] + 71.0 / 1920.0 * k4[i]
+                - 17253.0 / 339200.0 * k5[i] + 22.0 / 525.0 * k6[i] - 1.0 / 40.0 * k7[i]);
+            error = error.max(e.abs() / (tolerance * (1.0 + next[i].abs())));
+        }
+        if next.iter().any(|x| !x.is_finite()) {
+            error = f64::INFINITY;
+        }
+
+        // accept once the error is small enough, or the step cannot shrink any further
+        if error <= 1.0 || h <= dt * 1.0e-6 {
+            *state = next;
+            t += h;
+        }
+        let factor = if error == 0.0 { 5.0 } else { (0.9 * error.powf(-0.2)).clamp(0.2, 5.0) };
+        h = (h * factor).max(dt * 1.0e-6);
+    }
+}
+
+/// White noise current added to an engine's input, making its integration
+/// Euler-Maruyama: the noise is held for the step, so the charge it delivers,
+/// and with it the spread of the membrane potential, grows as `sqrt(dt)`.
+pub struct WhiteNoise {
+    sigma: Current,
+    rng: Stream,
+}
+
+impl WhiteNoise {
+    /// `sigma` is the standard deviation of the noise current averaged over 1 ms.
+    pub fn new(sigma: Current, rng: Stream) -> Self {
+        Self { sigma, rng }
+    }
+
+    /// Mean noise current over a step of `dt`.
+    pub fn sample(&mut self, dt: Time) -> Current {
+        let z: f64 = StandardNormal.sample(&mut self.rng);
+        self.sigma * (z / dt.millis().sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use super::*;
+    use crate::neuron::engine::NeuronEngine;
+    use crate::neuron::integrate_fire::IntegrateFire;
+    use crate::neuron::izhikevich::{Izhikevich, IzhikevichParams};
+    use crate::neuron::random::Seed;
+    use crate::neuron::network::Listeners;
+    use crate::neuron::units::{Current, Time};
+
+    // x' = -x + sin(t) written autonomously with t as the second variable
+    fn forced_decay(s: &[f64; 2]) -> [f64; 2] {
+        [-s[0] + s[1].sin(), 1.0]
+    }
+
+    fn exact_forced_decay(t: f64) -> f64 {
+        // x(0) = 1
+        1.5 * (-t).exp() + 0.5 * (t.sin() - t.cos())
+    }
+
+    fn error(integrator: Integrator, dt: f64) -> f64 {
+        let mut s = [1.0, 0.0];
+        let steps = (2.0 / dt).round() as usize;
+        for _ in 0..steps {
+            integrator.step(&mut s, dt, forced_decay);
+        }
+        (s[0] - exact_forced_decay(2.0)).abs()
+    }
+
+    // log2 of the error ratio when dt is halved
+    fn order(integrator: Integrator) -> f64 {
+        (error(integrator, 0.02) / error(integrator, 0.01)).log2()
+    }
+
+    #[test]
+    fn convergence_orders() {
+        assert!((order(Integrator::ForwardEuler) - 1.0).abs() < 0.1);
+        assert!((order(Integrator::HalfStep) - 1.0).abs() < 0.1);
+        assert!((order(Integrator::SemiImplicitEuler) - 1.0).abs() < 0.1);
+        assert!((order(Integrator::ExponentialEuler) - 1.0).abs() < 0.1);
+        assert!((order(Integrator::RungeKutta4) - 4.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn exponential_euler_is_exact_for_linear_decay() {
+        let mut s = [1.0];
+        Integrator::ExponentialEuler.step(&mut s, 5.0, |s| [2.0 - 0.5 * s[0]]);
+        assert!((s[0] - (4.0 - 3.0 * (-2.5f64).exp())).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn crossing_of_a_cubic() {
+        // v(t) = t^3 over a unit step, crossing 0.125 at t = 0.5
+        let t = SpikeInterpolation::CubicHermite.crossing(0.125, 1.0, 0.0, 0.0, 1.0, 3.0);
+        assert!((t - 0.5).abs() < 1.0e-9);
+        let t = SpikeInterpolation::Linear.crossing(0.125, 1.0, 0.0, 0.0, 1.0, 3.0);
+        assert!((t - 0.125).abs() < 1.0e-9);
+        assert_eq!(SpikeInterpolation::EndOfStep.crossing(0.125, 1.0, 0.0, 0.0, 1.0, 3.0), 1.0);
+    }
+
+    #[test]
+    fn adaptive_rk45_meets_tolerance_with_large_steps() {
+        let rk45 = Integrator::RungeKutta45 { tolerance: 1.0e-8 };
+        assert!(error(rk45, 1.0) < 1.0e-6);
+        assert!(error(rk45, 0.5) < 1.0e-6);
+    }
+
+    // membrane potential after 40 ms of subthreshold drive
+    fn izhikevich_v(integrator: Integrator, dt: f64) -> f64 {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut model = Izhikevich::new(IzhikevichParams::tonic_spiking);
+        model.set_integrator(integrator);
+        for _ in 0..(40.0 / dt).round() as usize {
+            model.receive(Current::from_picoamps(2.0));
+            assert!(model.step(listeners.clone(), Time::from_millis(dt)).0.is_none());
+        }
+        model.v
+    }
+
+    #[test]
+    fn izhikevich_converges_as_dt_shrinks() {
+        let reference = izhikevich_v(Integrator::RungeKutta4, 0.001);
+        for integrator in [Integrator::ForwardEuler, Integrator::HalfStep, Integrator::SemiImplicitEuler,
+                           Integrator::RungeKutta4, Integrator::ExponentialEuler,
+                           Integrator::RungeKutta45 { tolerance: 1.0e-6 }] {
+            let errors: Vec<f64> = [0.4, 0.2, 0.1, 0.05].iter()
+                .map(|&dt| (izhikevich_v(integrator, dt) - reference).abs())
+                .collect();
+            assert!(errors.windows(2).all(|e| e[1] <= e[0] + 1.0e-9), "{:?} {:?}", integrator, errors);
+            assert!(errors[3] < 0.05, "{:?} {:?}", integrator, errors);
+        }
+    }
+
+    // standard deviation of the potential of a noisy, subthreshold LIF cell
+    fn lif_spread(dt: f64) -> f64 {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut lif = IntegrateFire::default();
+        lif.set_noise(Some(WhiteNoise::new(Current::from_nanoamps(0.1), Seed::new(9).stream())));
+        let steps = (20_000.0 / dt) as usize;
+        let v: Vec<f64> = (0..steps).map(|_| {
+            lif.step(listeners.clone(), Time::from_millis(dt));
+            lif.membrane_potential
+        }).collect();
+        let mean = v.iter().sum::<f64>() / v.len() as f64;
+        (v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / v.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn white_noise_spread_is_independent_of_dt() {
+        // R sigma = 10 mV over 1 ms into a 20 ms membrane: sd = 10 / sqrt(2 * 20) mV
+        let expected = 10.0 / 40.0f64.sqrt();
+        for dt in [0.01, 0.1, 0.5] {
+            let sd = lif_spread(dt);
+            assert!((sd - expected).abs() < 0.1 * expected, "dt {} sd {} expected {}", dt, sd, expected);
+        }
+    }
+}
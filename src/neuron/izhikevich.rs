@@ -1,24 +1,62 @@
+#![allow(dead_code)]
+
 use std::cell::RefCell;
 use std::rc::Rc;
-use serde::Serialize;
-use strum_macros::EnumString;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, EnumString};
 
+/// Izhikevich (2003) simple model, in mV and ms. Injected current is read in pA
+/// with the membrane capacitance taken as 1 pF, which is how the paper's `I` is scaled.
 #[derive(Serialize)]
 pub struct Izhikevich {
     pub v: f64,
     pub u: f64,
-    threshold: f64,
-    a: f64,
-    b: f64,
-    reset_potential: f64,
-    d: f64,
+    constants: IzhikevichConstants,
+    input_current: Current,
+    #[serde(skip_serializing)]
+    ports: Ports,
+    #[serde(skip_serializing)]
+    integrator: Integrator,
+    #[serde(skip_serializing)]
+    interpolation: SpikeInterpolation,
     #[serde(skip_serializing)]
-    sg: Box<dyn SpikeGenerator>,
-    input_current: f64,
+    noise: Option<WhiteNoise>,
+}
+
+/// Parameters of `v' = 0.04 v^2 + 5 v + 140 - u + I`, `u' = a (b v - u)`,
+/// with `v = c, u = u + d` once `v >= threshold`, and the state the cell
+/// starts from and returns to on `reset`.
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub struct IzhikevichConstants {
+    /// recovery timescale
+    pub a: f64,
+    /// sensitivity of `u` to `v`
+    pub b: f64,
+    /// reset potential
+    pub c: f64,
+    /// recovery increment per spike
+    pub d: f64,
+    pub threshold: f64,
+    pub v0: f64,
+    pub u0: f64,
+    #[serde(default)]
+    pub form: IzhikevichForm,
+}
+
+/// Variations on the equations used by some cells of the 2004 figure.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IzhikevichForm {
+    #[default]
+    Standard,
+    /// `v' = 0.04 v^2 + 4.1 v + 108 - u + I`, for Class 1 excitability and the integrator.
+    Shifted,
+    /// `u' = a b (v + 65)`, so `u` keeps no memory of its own, for accommodation.
+    Accommodation,
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString, EnumIter)]
 pub enum IzhikevichParams {
     tonic_spiking,
     phasic_spiking,
@@ -44,91 +82,323 @@ pub enum IzhikevichParams {
     fast_spiking,
 }
 use IzhikevichParams::*;
-use crate::neuron::engine::{NeuronEngine, SpikeGenerator};
+use crate::neuron::engine::{NeuronEngine, PhasePlane};
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
 use crate::neuron::network::Listeners;
+use crate::neuron::protocol::Protocol;
+use crate::neuron::synapse::{Ports, Receptor};
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
 
-impl Izhikevich {
-    pub fn new(sg: Box<dyn SpikeGenerator>, params: IzhikevichParams) -> Self {
+impl IzhikevichParams {
+    /// Parameters and initial state of each cell as set in figure1.m, the
+    /// MATLAB script behind Izhikevich (2004). Class 1 excitability and the
+    /// integrator use the script's shifted `v` equation, and accommodation its
+    /// `u` equation; see `IzhikevichForm`.
+    pub fn constants(&self) -> IzhikevichConstants {
         let mut a = 0.02;
         let mut b = 0.2;
+        #[allow(unused_assignments)]
         let mut c = -80.0;
         #[allow(unused_assignments)]
         let mut d = -8.0;
         let mut v0 = -70.0;
         let mut u_override = 0.0;
-        match params {
+        let mut form = IzhikevichForm::Standard;
+        match self {
             tonic_spiking => {c=-65.0;d=6.0;},
             phasic_spiking => {b=0.25;c=-65.0;d=6.0;v0=-64.0;},
             tonic_bursting => {c=-50.0;d=2.0},
-            phasic_bursting => {b=0.25;c=-55.0;d=0.05},
+            phasic_bursting => {b = 0.25; c = -55.0; d = 0.05; v0 = -64.0;},
             mixed_mode => {c=-55.0;d=4.0;},
-            spike_freq_adapt => {a=0.01;d=8.0; v0=-65.0},
-            Class_1_excit => {b=-0.1; c=-55.0;d=6.0; v0=-60.0},
-            Class_2_excit => {a=0.2;b=-0.26; c=-65.0;d=0.0; v0=-64.0},
+            spike_freq_adapt => {a = 0.01; c = -65.0; d = 8.0;},
+            Class_1_excit => {b = -0.1; c = -55.0; d = 6.0; v0 = -60.0; form = IzhikevichForm::Shifted;},
+            Class_2_excit => {a = 0.2; b = 0.26; c = -65.0; d = 0.0; v0 = -64.0;},
             spike_latency => {c=-65.0; d=6.0;},
             subthreshold_osc => {a=0.05;b=0.26;c=-60.0;d=0.0; v0=-62.0},
             resonator => {a=0.1;b=0.26; c=-60.0;d=-1.0; v0=-62.0},
-            integrator => {a=0.02;b=-0.1; c=-55.0;d=6.0; v0=-60.0},
+            integrator => {a = 0.02; b = -0.1; c = -55.0; d = 6.0; v0 = -60.0; form = IzhikevichForm::Shifted;},
             rebound_spike => {a=0.03;b=0.25;c=-60.0; d=4.0;v0=-64.0},
             rebound_burst => {a=0.03;b=0.25;c=-52.0;d=0.0; v0=-64.0},
             thresh_variability => {a=0.03;b=0.25; c=-60.0;d=4.0; v0=-64.0},
             bistability => {a=0.1;b=0.26; c=-60.0;d=0.0; v0=-61.0},
             DAP => {a=1.0;b=0.2;c=-60.0;d=-21.0;},
-            accomodation => {a=0.02;b=1.0;c=-55.0;d=4.0; v0=-65.0; u_override=-16.0},
-            inh_induced_sp => {a=0.02;b=-1.0; c=-60.0;d=8.0; v0=-63.8},
-            inh_induced_brst => {a=0.026;b=-1.0; c=-45.0;d=-2.0; v0=-63.8},
-            intrinsically_bursting => {a=0.1;c=-55.0;d=4.0;},
-            fast_spiking => {a=0.1;b=0.3;c=-65.0; d=2.0; v0 = -65.0}
+            accomodation => {a = 0.02; b = 1.0; c = -55.0; d = 4.0; v0 = -65.0; u_override = -16.0; form = IzhikevichForm::Accommodation;},
+            inh_induced_sp => {a = -0.02; b = -1.0; c = -60.0; d = 8.0; v0 = -63.8;},
+            inh_induced_brst => {a = -0.026; b = -1.0; c = -45.0; d = -2.0; v0 = -63.8;},
+            // not in the figure; the values of Izhikevich (2003), Fig. 2
+            intrinsically_bursting => {c = -55.0; d = 4.0;},
+            fast_spiking => {a = 0.1; c = -65.0; d = 2.0; v0 = -65.0;}
         }
 
-        let u = if u_override == 0.0 {b * v0} else {u_override};
+        let u0 = if u_override == 0.0 {b * v0} else {u_override};
 
+        IzhikevichConstants { a, b, c, d, threshold: 30.0, v0, u0, form }
+    }
+
+    /// The stimulus, duration and step of this cell's panel in the 2004 figure,
+    /// with currents in pA as in the script. `intrinsically_bursting` and
+    /// `fast_spiking` are not in the figure and get a plain step of current.
+    pub fn protocol(&self) -> Protocol {
+        fn pa(i: f64) -> Current {
+            Current::from_picoamps(i)
+        }
+        let protocol = |panel, duration, dt, current| Protocol {
+            panel,
+            duration: Time::from_millis(duration),
+            dt: Time::from_millis(dt),
+            current,
+        };
+        match self {
+            tonic_spiking => protocol(Some('A'), 100.0, 0.25, |t| {
+                pa(if t.millis() > 10.0 { 14.0 } else { 0.0 })
+            }),
+            phasic_spiking => protocol(Some('B'), 200.0, 0.25, |t| {
+                pa(if t.millis() > 20.0 { 0.5 } else { 0.0 })
+            }),
+            tonic_bursting => protocol(Some('C'), 220.0, 0.25, |t| {
+                pa(if t.millis() > 22.0 { 15.0 } else { 0.0 })
+            }),
+            phasic_bursting => protocol(Some('D'), 200.0, 0.2, |t| {
+                pa(if t.millis() > 20.0 { 0.6 } else { 0.0 })
+            }),
+            mixed_mode => protocol(Some('E'), 160.0, 0.25, |t| {
+                pa(if t.millis() > 16.0 { 10.0 } else { 0.0 })
+            }),
+            spike_freq_adapt => protocol(Some('F'), 85.0, 0.25, |t| {
+                pa(if t.millis() > 8.5 { 30.0 } else { 0.0 })
+            }),
+            Class_1_excit => protocol(Some('G'), 300.0, 0.25, |t| {
+                pa(if t.millis() > 30.0 { 0.075 * (t.millis() - 30.0) } else { 0.0 })
+            }),
+            Class_2_excit => protocol(Some('H'), 300.0, 0.25, |t| {
+                pa(if t.millis() > 30.0 { -0.5 + 0.015 * (t.millis() - 30.0) } else { -0.5 })
+            }),
+            spike_latency => protocol(Some('I'), 100.0, 0.2, |t| {
+                let t = t.millis();
+                pa(if t > 10.0 && t < 13.0 { 7.04 } else { 0.0 })
+            }),
+            subthreshold_osc => protocol(Some('J'), 200.0, 0.25, |t| {
+                let t = t.millis();
+                pa(if t > 20.0 && t < 25.0 { 2.0 } else { 0.0 })
+            }),
+            resonator => protocol(Some('K'), 400.0, 0.25, |t| {
+                let t = t.millis();
+                let pulse = [40.0, 60.0, 280.0, 320.0].iter().any(|&s| t > s && t < s + 4.0);
+                pa(if pulse { 0.65 } else { 0.0 })
+            }),
+            integrator => protocol(Some('L'), 100.0, 0.25, |t| {
+                let (t, t1) = (t.millis(), 100.0 / 11.0);
+                let pulse = [t1, t1 + 5.0, 70.0, 80.0].iter().any(|&s| t > s && t < s + 2.0);
+                pa(if pulse { 9.0 } else { 0.0 })
+            }),
+            rebound_spike => protocol(Some('M'), 200.0, 0.2, |t| {
+                let t = t.millis();
+                pa(if t > 20.0 && t < 25.0 { -15.0 } else { 0.0 })
+            }),
+            rebound_burst => protocol(Some('N'), 200.0, 0.2, |t| {
+                let t = t.millis();
+                pa(if t > 20.0 && t < 25.0 { -15.0 } else { 0.0 })
+            }),
+            thresh_variability => protocol(Some('O'), 100.0, 0.25, |t| {
+                let t = t.millis();
+                pa(if (t > 10.0 && t < 15.0) || (t > 80.0 && t < 85.0) {
+                    1.0
+                } else if t > 70.0 && t < 75.0 {
+                    -6.0
+                } else {
+                    0.0
+                })
+            }),
+            bistability => protocol(Some('P'), 300.0, 0.25, |t| {
+                let t = t.millis();
+                let pulse = [37.5, 216.0].iter().any(|&s| t > s && t < s + 5.0);
+                pa(if pulse { 1.24 } else { 0.24 })
+            }),
+            DAP => protocol(Some('Q'), 50.0, 0.1, |t| {
+                pa(if (t.millis() - 10.0).abs() < 1.0 { 20.0 } else { 0.0 })
+            }),
+            accomodation => protocol(Some('R'), 400.0, 0.5, |t| {
+                let t = t.millis();
+                pa(if t < 200.0 {
+                    t / 25.0
+                } else if t < 300.0 {
+                    0.0
+                } else if t < 312.5 {
+                    (t - 300.0) / 12.5 * 4.0
+                } else {
+                    0.0
+                })
+            }),
+            inh_induced_sp => protocol(Some('S'), 350.0, 0.5, |t| {
+                let t = t.millis();
+                pa(if (50.0..=250.0).contains(&t) { 75.0 } else { 80.0 })
+            }),
+            inh_induced_brst => protocol(Some('T'), 350.0, 0.5, |t| {
+                let t = t.millis();
+                pa(if (50.0..=250.0).contains(&t) { 75.0 } else { 80.0 })
+            }),
+            intrinsically_bursting | fast_spiking => protocol(None, 200.0, 0.25, |t| {
+                pa(if t.millis() > 20.0 { 10.0 } else { 0.0 })
+            }),
+        }
+    }
+}
+
+impl Izhikevich {
+    pub fn new(params: IzhikevichParams) -> Self {
+        Self::with_constants(params.constants())
+    }
+
+    pub fn with_constants(constants: IzhikevichConstants) -> Self {
         Izhikevich {
-            v: v0,
-            u: u,
-            threshold: 30.0,
-            a: a, // recovery-timescale
-            b: b, // senstivity of U to V
-            reset_potential: c,
-            d: d,
-            sg: sg,
-            input_current: 0.0
+            v: constants.v0,
+            u: constants.u0,
+            constants,
+            input_current: Current::ZERO,
+            ports: Ports::default(),
+            integrator: Integrator::default(),
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
         }
     }
+
+    pub fn constants(&self) -> &IzhikevichConstants {
+        &self.constants
+    }
+
+    /// Swaps the parameters mid-run. The state is left where it is; call
+    /// `reset` to start from the new initial conditions.
+    pub fn set_constants(&mut self, constants: IzhikevichConstants) {
+        self.constants = constants;
+    }
+
+    fn derivatives(&self, state: &[f64; 2], i: f64) -> [f64; 2] {
+        let (v, u) = (state[0], state[1]);
+        let p = &self.constants;
+        let dv = match p.form {
+            IzhikevichForm::Shifted => (0.04 * v * v) + (4.1 * v) + 108.0 - u + i,
+            _ => (0.04 * v * v) + (5.0 * v) + 140.0 - u + i,
+        };
+        let du = match p.form {
+            IzhikevichForm::Accommodation => p.a * p.b * (v + 65.0),
+            _ => p.a * ((p.b * v) - u),
+        };
+        [dv, du]
+    }
 }
 
 impl NeuronEngine for Izhikevich {
-    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: f64) -> (bool, f64) {
-        let i = self.sg.step(dt) + self.input_current;// * 8.0e12; // pA
-        self.input_current = 0.0;
-        let v2 = self.v * self.v;
-        let dv = (0.04 * v2) + (5.0 * self.v) + 140.0 - self.u + i;
-        let du = self.a * ((self.b * self.v) - self.u);
-        //println!("I {}, dv: {}, v2: {}, 5v: {}, du: {}", i, dv, v2, 5.0*self.v, du);
-        self.v += dv * dt;
-        self.u += du * dt;
-        let mut fired = false;
-        if self.v >= self.threshold {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.picoamps(), dt.millis());
+        let start = [self.v, self.u];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        let (v, synaptic) = self.ports.relax(Voltage::from_millivolts(state[0]), Time::from_millis(dt), Capacitance::from_picofarads(1.0));
+        state[0] = v.millivolts();
+        [self.v, self.u] = state;
+        let mut spike = None;
+        let p = self.constants;
+        if self.v >= p.threshold {
             // spike
             //println!("spike {} u {}", self.v, self.u);
-            fired = true;
-            listeners.borrow().inform();
-            self.v = self.reset_potential;
-            self.u += self.d;
+            let offset = Time::from_millis(self.interpolation.crossing(p.threshold, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+            self.v = p.c;
+            self.u += p.d;
         }
 
-        (fired, i)
+        (spike, current + synaptic)
     }
 
     fn reset(&mut self) {
-        todo!()
+        self.v = self.constants.v0;
+        self.u = self.constants.u0;
+        self.input_current = Current::ZERO;
+        self.ports.close();
     }
 
-    fn receive(&mut self, curr: f64) {
+    fn receive(&mut self, curr: Current) {
         self.input_current += curr;
     }
 
-    fn get_membrane_potential(&self) -> f64 {
-        self.v * 1.0e-3
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        self.ports.open(receptor, g);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.v)
+    }
+
+    // `integrator` is taken by the preset of the same name
+    fn set_integrator(&mut self, method: Integrator) {
+        self.integrator = method;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
     }
-}
\ No newline at end of file
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+impl PhasePlane for Izhikevich {
+    fn phase(&self) -> (f64, f64) {
+        (self.v, self.u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spike_count(model: &mut Izhikevich, steps: usize) -> usize {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        (0..steps).filter(|_| {
+            model.receive(Current::from_picoamps(10.0));
+            model.step(listeners.clone(), Time::from_millis(0.1)).0.is_some()
+        }).count()
+    }
+
+    #[test]
+    fn builds_from_custom_constants() {
+        let constants = IzhikevichConstants { d: 2.0, v0: -60.0, u0: -12.0, ..tonic_spiking.constants() };
+        let model = Izhikevich::with_constants(constants);
+        assert_eq!((model.v, model.u), (-60.0, -12.0));
+        assert_eq!(model.constants().d, 2.0);
+    }
+
+    #[test]
+    fn constants_change_at_runtime_and_reset_restores_state() {
+        let mut model = Izhikevich::new(tonic_spiking);
+        let before = spike_count(&mut model, 5000);
+        model.set_constants(IzhikevichConstants { d: 12.0, ..*model.constants() });
+        let after = spike_count(&mut model, 5000);
+        assert!(after < before, "{} spikes before, {} after", before, after);
+
+        model.reset();
+        assert_eq!((model.v, model.u), (-70.0, -14.0));
+    }
+
+    #[test]
+    fn large_conductances_do_not_pass_reversal() {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let reversal = Receptor::GabaB.reversal_potential().millivolts();
+        // at 30 nS the time constant is far below the step
+        for g in [15.0, 30.0, 1000.0] {
+            let mut model = Izhikevich::new(tonic_spiking);
+            for _ in 0..2000 {
+                model.receive(Current::from_picoamps(10.0));
+                model.receive_conductance(Receptor::GabaB, Conductance::from_nanosiemens(g));
+                assert!(model.step(listeners.clone(), Time::from_millis(0.1)).0.is_none(), "spike at {} nS", g);
+                assert!(model.v >= reversal - 1.0e-9, "{} mV at {} nS", model.v, g);
+            }
+        }
+    }
+}
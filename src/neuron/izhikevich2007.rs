@@ -0,0 +1,261 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use strum_macros::EnumString;
+use crate::neuron::engine::NeuronEngine;
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::{Ports, Receptor};
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
+
+/// Shape of the slow current nullcline `U(v)` in `u' = a * (U(v) - u)`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Recovery {
+    /// `U(v) = b * (v - vr)`, the only form NeuroML's `izhikevich2007Cell` supports.
+    Linear,
+    /// `U(v) = b * (v - v_b)^3` above `v_b` and zero below it (fast spiking interneurons).
+    Cubic { v_b: f64 },
+    /// `b` applies at or below `v_b`, `b_depolarized` above it (thalamic cells).
+    Switching { v_b: f64, b_depolarized: f64 },
+}
+
+/// Parameters of the 2007 formulation of the Izhikevich model:
+/// `C v' = k (v - vr)(v - vt) - u + I`, `u' = a (b (v - vr) - u)`,
+/// with `v = c, u = u + d` once `v >= vpeak`.
+///
+/// Units follow NeuroML: pF, nS/mV for `k`, mV, 1/ms for `a`, nS for `b` and pA.
+/// `peak_u` and `reset_u` make `vpeak` and `c` depend on `u` as some cells in
+/// the book require; both are zero for a plain NeuroML cell.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Izhikevich2007Constants {
+    pub capacitance: f64,
+    pub k: f64,
+    pub vr: f64,
+    pub vt: f64,
+    pub vpeak: f64,
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub recovery: Recovery,
+    pub peak_u: f64,
+    pub reset_u: f64,
+}
+
+impl Izhikevich2007Constants {
+    /// Takes the attributes of a NeuroML `izhikevich2007Cell` in document order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(capacitance: f64, k: f64, vr: f64, vt: f64, vpeak: f64,
+               a: f64, b: f64, c: f64, d: f64) -> Self {
+        Self {
+            capacitance,
+            k,
+            vr,
+            vt,
+            vpeak,
+            a,
+            b,
+            c,
+            d,
+            recovery: Recovery::Linear,
+            peak_u: 0.0,
+            reset_u: 0.0,
+        }
+    }
+}
+
+/// Cell types from chapter 8 of Izhikevich, "Dynamical Systems in Neuroscience" (2007).
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString)]
+pub enum Izhikevich2007Params {
+    regular_spiking,        // RS
+    intrinsically_bursting, // IB
+    chattering,             // CH
+    fast_spiking,           // FS
+    low_threshold_spiking,  // LTS
+    thalamocortical,        // TC
+    reticular_thalamic,     // RTN
+}
+use Izhikevich2007Params::*;
+
+impl Izhikevich2007Params {
+    pub fn constants(&self) -> Izhikevich2007Constants {
+        type C = Izhikevich2007Constants;
+        match self {
+            regular_spiking => C::new(100.0, 0.7, -60.0, -40.0, 35.0, 0.03, -2.0, -50.0, 100.0),
+            intrinsically_bursting => C::new(150.0, 1.2, -75.0, -45.0, 50.0, 0.01, 5.0, -56.0, 130.0),
+            chattering => C::new(50.0, 1.5, -60.0, -40.0, 25.0, 0.03, 1.0, -40.0, 150.0),
+            fast_spiking => C {
+                recovery: Recovery::Cubic { v_b: -55.0 },
+                ..C::new(20.0, 1.0, -55.0, -40.0, 25.0, 0.2, 0.025, -45.0, 0.0)
+            },
+            low_threshold_spiking => C {
+                peak_u: -0.1,
+                reset_u: 0.04,
+                ..C::new(100.0, 1.0, -56.0, -42.0, 40.0, 0.03, 8.0, -53.0, 20.0)
+            },
+            thalamocortical => C {
+                recovery: Recovery::Switching { v_b: -65.0, b_depolarized: 0.0 },
+                peak_u: 0.1,
+                reset_u: -0.1,
+                ..C::new(200.0, 1.6, -60.0, -50.0, 35.0, 0.01, 15.0, -60.0, 10.0)
+            },
+            reticular_thalamic => C {
+                recovery: Recovery::Switching { v_b: -65.0, b_depolarized: 2.0 },
+                ..C::new(40.0, 0.25, -65.0, -45.0, 0.0, 0.015, 10.0, -55.0, 50.0)
+            },
+        }
+    }
+}
+
+/// The dimensional Izhikevich (2007) simple model.
+pub struct Izhikevich2007 {
+    pub v: f64,
+    pub u: f64,
+    constants: Izhikevich2007Constants,
+    input_current: Current,
+    ports: Ports,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl Izhikevich2007 {
+    pub fn new(params: Izhikevich2007Params) -> Self {
+        Self::with_constants(params.constants())
+    }
+
+    pub fn with_constants(constants: Izhikevich2007Constants) -> Self {
+        Izhikevich2007 {
+            v: constants.vr,
+            u: 0.0,
+            constants,
+            input_current: Current::ZERO,
+            ports: Ports::default(),
+            integrator: Integrator::default(),
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        }
+    }
+
+    pub fn constants(&self) -> &Izhikevich2007Constants {
+        &self.constants
+    }
+
+    fn recovery_target(&self, v: f64) -> f64 {
+        let p = &self.constants;
+        match p.recovery {
+            Recovery::Linear => p.b * (v - p.vr),
+            Recovery::Cubic { v_b } => {
+                if v > v_b { p.b * (v - v_b).powi(3) } else { 0.0 }
+            }
+            Recovery::Switching { v_b, b_depolarized } => {
+                let b = if v > v_b { b_depolarized } else { p.b };
+                b * (v - p.vr)
+            }
+        }
+    }
+
+    fn derivatives(&self, state: &[f64; 2], i: f64) -> [f64; 2] {
+        let p = &self.constants;
+        let (v, u) = (state[0], state[1]);
+        let dv = (p.k * (v - p.vr) * (v - p.vt) - u + i) / p.capacitance;
+        let du = p.a * (self.recovery_target(v) - u);
+        [dv, du]
+    }
+}
+
+impl NeuronEngine for Izhikevich2007 {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.picoamps(), dt.millis());
+        let start = [self.v, self.u];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        let (v, synaptic) = self.ports.relax(Voltage::from_millivolts(state[0]), Time::from_millis(dt), Capacitance::from_picofarads(self.constants.capacitance));
+        state[0] = v.millivolts();
+        [self.v, self.u] = state;
+        let p = self.constants;
+
+        let mut spike = None;
+        let peak = p.vpeak + p.peak_u * self.u;
+        if self.v >= peak {
+            let offset = Time::from_millis(self.interpolation.crossing(peak, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+            self.v = p.c + p.reset_u * self.u;
+            self.u += p.d;
+        }
+        (spike, current + synaptic)
+    }
+
+    fn reset(&mut self) {
+        self.v = self.constants.vr;
+        self.u = 0.0;
+        self.input_current = Current::ZERO;
+        self.ports.close();
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        self.ports.open(receptor, g);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.v)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spike_count(params: Izhikevich2007Params, current: f64) -> usize {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut cell = Izhikevich2007::new(params);
+        (0..10000).filter(|_| {
+            cell.receive(Current::from_picoamps(current));
+            cell.step(listeners.clone(), Time::from_millis(0.1)).0.is_some()
+        }).count()
+    }
+
+    #[test]
+    fn rests_at_vr() {
+        for params in [regular_spiking, intrinsically_bursting, chattering, fast_spiking,
+                       low_threshold_spiking, thalamocortical, reticular_thalamic] {
+            assert_eq!(spike_count(params, 0.0), 0, "{:?}", params);
+        }
+    }
+
+    #[test]
+    fn regular_spiking_fires_above_rheobase() {
+        assert_eq!(spike_count(regular_spiking, 30.0), 0);
+        assert!(spike_count(regular_spiking, 100.0) > 5);
+    }
+
+    #[test]
+    fn fast_spiking_outpaces_regular_spiking() {
+        let rs = spike_count(regular_spiking, 200.0);
+        let fs = spike_count(fast_spiking, 200.0);
+        assert!(fs > 2 * rs, "fs {} rs {}", fs, rs);
+    }
+}
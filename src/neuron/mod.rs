@@ -1,3 +1,22 @@
+pub mod adex;
+pub mod analysis;
+pub mod device;
 pub mod engine;
+pub mod fitzhugh_nagumo;
+pub mod hindmarsh_rose;
+pub mod hodgkins_huxley;
+pub mod integrate_fire;
+pub mod integrator;
 pub mod izhikevich;
-pub mod network;
\ No newline at end of file
+pub mod izhikevich2007;
+pub mod morris_lecar;
+pub mod network;
+pub mod playback;
+pub mod poisson;
+pub mod presets;
+pub mod protocol;
+pub mod random;
+pub mod spike_file;
+pub mod stimulus;
+pub mod synapse;
+pub mod units;
\ No newline at end of file
@@ -0,0 +1,242 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use strum_macros::EnumString;
+use crate::neuron::engine::{NeuronEngine, PhasePlane};
+use crate::neuron::integrator::{Integrator, SpikeInterpolation, WhiteNoise};
+use crate::neuron::network::Listeners;
+use crate::neuron::synapse::{Ports, Receptor};
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
+
+/// Parameters of the Morris-Lecar model.
+///
+/// Units: mV, ms, mS/cm^2, uF/cm^2 and uA/cm^2 for injected current.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct MorrisLecarConstants {
+    pub c: f64,
+    pub g_l: f64,
+    pub g_ca: f64,
+    pub g_k: f64,
+    pub e_l: f64,
+    pub e_ca: f64,
+    pub e_k: f64,
+    pub v1: f64,
+    pub v2: f64,
+    pub v3: f64,
+    pub v4: f64,
+    pub phi: f64,
+}
+
+/// The three onset bifurcations in Ermentrout & Terman,
+/// "Mathematical Foundations of Neuroscience" (2010), table 3.1.
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumString)]
+pub enum MorrisLecarParams {
+    hopf,
+    snlc,
+    homoclinic,
+}
+use MorrisLecarParams::*;
+
+impl MorrisLecarParams {
+    pub fn constants(&self) -> MorrisLecarConstants {
+        let (g_ca, v3, v4, phi) = match self {
+            hopf => (4.4, 2.0, 30.0, 0.04),
+            snlc => (4.0, 12.0, 17.4, 1.0 / 15.0),
+            homoclinic => (4.0, 12.0, 17.4, 0.23),
+        };
+        MorrisLecarConstants {
+            c: 20.0,
+            g_l: 2.0,
+            g_ca,
+            g_k: 8.0,
+            e_l: -60.0,
+            e_ca: 120.0,
+            e_k: -84.0,
+            v1: -1.2,
+            v2: 18.0,
+            v3,
+            v4,
+            phi,
+        }
+    }
+}
+
+/// Morris & Lecar (1981) barnacle muscle fiber model with an instantaneous
+/// calcium current and a slow potassium gate `w`.
+///
+/// Injected current is divided by `area` to get uA/cm^2; the default of
+/// 1e-3 cm^2 makes 1 nA a density of 1 uA/cm^2.
+pub struct MorrisLecar {
+    pub v: f64,
+    pub w: f64,
+    /// Membrane area in cm^2.
+    pub area: f64,
+    constants: MorrisLecarConstants,
+    threshold: f64,
+    input_current: Current,
+    ports: Ports,
+    integrator: Integrator,
+    interpolation: SpikeInterpolation,
+    noise: Option<WhiteNoise>,
+}
+
+impl MorrisLecar {
+    pub fn new(params: MorrisLecarParams) -> Self {
+        Self::with_constants(params.constants())
+    }
+
+    pub fn with_constants(constants: MorrisLecarConstants) -> Self {
+        let mut model = MorrisLecar {
+            v: 0.0,
+            w: 0.0,
+            area: 1.0e-3,
+            constants,
+            threshold: 0.0,
+            input_current: Current::ZERO,
+            ports: Ports::default(),
+            integrator: Integrator::default(),
+            interpolation: SpikeInterpolation::default(),
+            noise: None,
+        };
+        model.reset();
+        model
+    }
+
+    pub fn constants(&self) -> &MorrisLecarConstants {
+        &self.constants
+    }
+
+    fn m_inf(&self, v: f64) -> f64 {
+        let p = &self.constants;
+        0.5 * (1.0 + ((v - p.v1) / p.v2).tanh())
+    }
+
+    fn w_inf(&self, v: f64) -> f64 {
+        let p = &self.constants;
+        0.5 * (1.0 + ((v - p.v3) / p.v4).tanh())
+    }
+
+    fn tau_w(&self, v: f64) -> f64 {
+        let p = &self.constants;
+        1.0 / ((v - p.v3) / (2.0 * p.v4)).cosh()
+    }
+
+    fn ionic_current(&self, v: f64, w: f64) -> f64 {
+        let p = &self.constants;
+        p.g_l * (v - p.e_l) + p.g_ca * self.m_inf(v) * (v - p.e_ca) + p.g_k * w * (v - p.e_k)
+    }
+
+    // the most hyperpolarized fixed point without input, found by bisection
+    fn rest(&self) -> f64 {
+        let f = |v: f64| self.ionic_current(v, self.w_inf(v));
+        let mut lo = -100.0;
+        while f(lo + 1.0) < 0.0 && lo < 100.0 {
+            lo += 1.0;
+        }
+        let mut hi = lo + 1.0;
+        for _ in 0..60 {
+            let mid = 0.5 * (lo + hi);
+            if f(mid) < 0.0 { lo = mid } else { hi = mid }
+        }
+        0.5 * (lo + hi)
+    }
+
+    fn derivatives(&self, state: &[f64; 2], i: f64) -> [f64; 2] {
+        let (v, w) = (state[0], state[1]);
+        let dv = (i - self.ionic_current(v, w)) / self.constants.c;
+        let dw = self.constants.phi * (self.w_inf(v) - w) / self.tau_w(v);
+        [dv, dw]
+    }
+}
+
+impl NeuronEngine for MorrisLecar {
+    fn step(&mut self, listeners: Rc<RefCell<Listeners>>, dt: Time) -> (Option<Time>, Current) {
+        let noise = self.noise.as_mut().map_or(Current::ZERO, |n| n.sample(dt));
+        let current = self.input_current + noise;
+        self.input_current = Current::ZERO;
+        let (i, dt) = (current.microamps() / self.area, dt.millis());
+        let start = [self.v, self.w];
+        let mut state = start;
+        self.integrator.step(&mut state, dt, |s| self.derivatives(s, i));
+        let (v, synaptic) = self.ports.relax(Voltage::from_millivolts(state[0]), Time::from_millis(dt), Capacitance::from_microfarads(self.constants.c * self.area));
+        state[0] = v.millivolts();
+        [self.v, self.w] = state;
+
+        let mut spike = None;
+        if start[0] < self.threshold && state[0] >= self.threshold {
+            let offset = Time::from_millis(self.interpolation.crossing(self.threshold, dt,
+                start[0], self.derivatives(&start, i)[0], state[0], self.derivatives(&state, i)[0]));
+            spike = Some(offset);
+            listeners.borrow().inform(offset);
+        }
+        (spike, current + synaptic)
+    }
+
+    fn reset(&mut self) {
+        self.v = self.rest();
+        self.w = self.w_inf(self.v);
+        self.input_current = Current::ZERO;
+        self.ports.close();
+    }
+
+    fn receive(&mut self, curr: Current) {
+        self.input_current += curr;
+    }
+
+    fn receive_conductance(&mut self, receptor: Receptor, g: Conductance) {
+        self.ports.open(receptor, g);
+    }
+
+    fn get_membrane_potential(&self) -> Voltage {
+        Voltage::from_millivolts(self.v)
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    fn set_spike_interpolation(&mut self, interpolation: SpikeInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    fn set_noise(&mut self, noise: Option<WhiteNoise>) {
+        self.noise = noise;
+    }
+}
+
+impl PhasePlane for MorrisLecar {
+    fn phase(&self) -> (f64, f64) {
+        (self.v, self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spike_count(params: MorrisLecarParams, current: f64) -> usize {
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut ml = MorrisLecar::new(params);
+        (0..20000).filter(|_| {
+            ml.receive(Current::from_nanoamps(current));
+            ml.step(listeners.clone(), Time::from_millis(0.05)).0.is_some()
+        }).count()
+    }
+
+    #[test]
+    fn rests_without_input() {
+        for params in [hopf, snlc, homoclinic] {
+            let ml = MorrisLecar::new(params);
+            assert!(ml.v < -50.0, "{:?} rests at {}", params, ml.v);
+            assert_eq!(spike_count(params, 0.0), 0, "{:?}", params);
+        }
+    }
+
+    #[test]
+    fn fires_with_strong_input() {
+        assert!(spike_count(hopf, 100.0) > 3);
+        assert!(spike_count(snlc, 50.0) > 3);
+    }
+}
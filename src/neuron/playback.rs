@@ -0,0 +1,240 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Context};
+use crate::neuron::engine::SpikeGenerator;
+use crate::neuron::units::{Current, Time};
+
+/// Plays back a sampled current waveform, such as a recorded current-clamp
+/// protocol.
+///
+/// Samples are joined by straight lines and each step returns the mean of
+/// that line over the step, so the waveform is resampled correctly whether
+/// `dt` is finer or coarser than the recording. After the last sample the
+/// output is zero, or the recording starts again if looping. A recording of
+/// a single sample is held at that value.
+pub struct Playback {
+    samples: Vec<Current>,
+    // running integral of the interpolated waveform at each sample, in A s
+    integral: Vec<f64>,
+    sample_period: Time,
+    looping: bool,
+    gain: f64,
+    counter: Time,
+}
+
+impl Playback {
+    /// `sample_rate` in Hz.
+    pub fn new(samples: Vec<Current>, sample_rate: f64) -> Self {
+        let sample_period = Time::from_seconds(1.0 / sample_rate);
+        let mut integral = vec![0.0];
+        for pair in samples.windows(2) {
+            let area = 0.5 * (pair[0].amps() + pair[1].amps()) * sample_period.seconds();
+            integral.push(integral[integral.len() - 1] + area);
+        }
+        Self {
+            samples,
+            integral,
+            sample_period,
+            looping: false,
+            gain: 1.0,
+            counter: Time::ZERO,
+        }
+    }
+
+    /// Restart from the first sample after the last one.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Multiply every sample by `gain`.
+    pub fn gain(mut self, gain: f64) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Reads one column of a comma or whitespace separated file, one sample per
+    /// line, each value being a multiple of `unit`. Blank lines and lines
+    /// starting with `#` are skipped, as is the first other line if it does
+    /// not parse; any later line that does not is an error.
+    pub fn from_csv(path: impl AsRef<Path>, column: usize, sample_rate: f64, unit: Current) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut samples = Vec::new();
+        let lines = text.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+        let mut first = true;
+        for (n, line) in lines {
+            let header = std::mem::replace(&mut first, false);
+            let field = line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|f| !f.is_empty())
+                .nth(column)
+                .ok_or_else(|| anyhow!("{}:{} has no column {}", path.display(), n + 1, column))?;
+            match field.parse::<f64>() {
+                Ok(value) => samples.push(unit * value),
+                // the first line may be a header
+                Err(_) if header => continue,
+                Err(e) => return Err(anyhow!("{}:{}: {:?} {}", path.display(), n + 1, field, e)),
+            }
+        }
+        if samples.is_empty() {
+            return Err(anyhow!("{} has no samples", path.display()));
+        }
+        Ok(Self::new(samples, sample_rate))
+    }
+
+    /// Reads the first channel of a WAV file at its own sample rate. Integer
+    /// samples are scaled to [-1, 1] and `full_scale` is the current at 1.
+    pub fn from_wav(path: impl AsRef<Path>, full_scale: Current) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut reader = hound::WavReader::open(path).with_context(|| format!("reading {}", path.display()))?;
+        let spec = reader.spec();
+        let channels = spec.channels as usize;
+        let values: Vec<f64> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>()
+                .map(|s| s.map(f64::from))
+                .collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f64;
+                reader.samples::<i32>()
+                    .map(|s| s.map(|s| s as f64 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let samples = values.iter().step_by(channels).map(|&v| full_scale * v).collect();
+        Ok(Self::new(samples, spec.sample_rate as f64))
+    }
+
+    pub fn duration(&self) -> Time {
+        self.sample_period * (self.samples.len().saturating_sub(1)) as f64
+    }
+
+    // integral of the waveform from the start to `t`, in A s
+    fn integral_to(&self, t: Time) -> f64 {
+        if self.samples.len() == 1 {
+            return self.samples[0].amps() * t.seconds();
+        }
+        let total = self.integral[self.integral.len() - 1];
+        let duration = self.duration();
+        let (laps, t) = if self.looping && duration > Time::ZERO {
+            let laps = (t / duration).floor();
+            (laps, t - duration * laps)
+        } else {
+            (0.0, if t < duration { t } else { duration })
+        };
+        let position = t / self.sample_period;
+        let n = (position.floor() as usize).min(self.samples.len() - 1);
+        let mut area = laps * total + self.integral[n];
+        if n + 1 < self.samples.len() {
+            // part of the trapezoid between sample n and t
+            let frac = position - n as f64;
+            let (a, b) = (self.samples[n].amps(), self.samples[n + 1].amps());
+            area += (a + 0.5 * (b - a) * frac) * frac * self.sample_period.seconds();
+        }
+        area
+    }
+}
+
+impl SpikeGenerator for Playback {
+    fn step(&mut self, dt: Time) -> Current {
+        let mean = (self.integral_to(self.counter + dt) - self.integral_to(self.counter)) / dt.seconds();
+        self.counter += dt;
+        Current::from_amps(mean) * self.gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pa(x: f64) -> Current {
+        Current::from_picoamps(x)
+    }
+
+    fn trace(playback: &mut Playback, dt: f64, steps: usize) -> Vec<f64> {
+        (0..steps).map(|_| playback.step(Time::from_millis(dt)).picoamps()).collect()
+    }
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1.0e-9)
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        // 0, 10, 20 pA at 1 kHz, read in 0.5 ms steps: means of each half sample
+        let mut p = Playback::new(vec![pa(0.0), pa(10.0), pa(20.0)], 1000.0);
+        assert!(close(&trace(&mut p, 0.5, 6), &[2.5, 7.5, 12.5, 17.5, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn coarse_steps_average_the_recording() {
+        // a 1 kHz square wave seen in 1 ms steps averages to its mean
+        let samples: Vec<Current> = (0..=100).map(|n| pa(if (n / 5) % 2 == 0 { 10.0 } else { -10.0 })).collect();
+        let mut p = Playback::new(samples, 10_000.0);
+        for i in trace(&mut p, 1.0, 10) {
+            assert!(i.abs() < 1.0, "{}", i);
+        }
+    }
+
+    #[test]
+    fn loops_and_scales() {
+        let mut p = Playback::new(vec![pa(0.0), pa(10.0)], 1000.0).looping(true).gain(2.0);
+        assert!(close(&trace(&mut p, 0.5, 4), &[5.0, 15.0, 5.0, 15.0]));
+    }
+
+    #[test]
+    fn reads_csv_with_header() {
+        let path = std::env::temp_dir().join("neuron_playback_test.csv");
+        fs::write(&path, "time,current\n# comment\n0.0, 1\n0.1, 2\n\n0.2, 3\n").unwrap();
+        let mut p = Playback::from_csv(&path, 1, 10_000.0, Current::from_nanoamps(1.0)).unwrap();
+        assert!((p.duration().millis() - 0.2).abs() < 1.0e-12);
+        assert!(close(&trace(&mut p, 0.1, 2), &[1500.0, 2500.0]));
+        fs::write(&path, "0.0, 1\n0.1, x\n").unwrap();
+        assert!(Playback::from_csv(&path, 1, 10_000.0, Current::from_nanoamps(1.0)).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn only_one_header_line_is_skipped() {
+        let path = std::env::temp_dir().join("neuron_playback_headers.csv");
+        fs::write(&path, "time,current
+# comment
+seconds,nA
+0.0, 1
+").unwrap();
+        let error = Playback::from_csv(&path, 1, 10_000.0, Current::from_nanoamps(1.0)).err().unwrap();
+        assert!(error.to_string().contains(":3:"), "{}", error);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn single_sample_is_held() {
+        let mut p = Playback::new(vec![pa(5.0)], 1000.0);
+        assert!(close(&trace(&mut p, 0.3, 4), &[5.0; 4]));
+        let mut p = Playback::new(vec![pa(5.0)], 1000.0).looping(true).gain(2.0);
+        assert!(close(&trace(&mut p, 0.3, 4), &[10.0; 4]));
+    }
+
+    #[test]
+    fn reads_wav() {
+        let path = std::env::temp_dir().join("neuron_playback_test.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 1000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for s in [0i16, 16384, i16::MIN, 0] {
+            writer.write_sample(s).unwrap();
+            writer.write_sample(1234i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        // first channel is 0, 0.5, -1, 0 of a 100 pA full scale
+        let mut p = Playback::from_wav(&path, pa(100.0)).unwrap();
+        assert!(close(&trace(&mut p, 0.5, 4), &[12.5, 37.5, 12.5, -62.5]));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+#![allow(dead_code)]
+
+use rand_distr::{Distribution, Exp1};
+use crate::neuron::engine::SpikeGenerator;
+use crate::neuron::random::Stream;
+use crate::neuron::synapse::{Synapse, SynapticInput};
+use crate::neuron::units::{Current, Time};
+
+/// Poisson spike train, at a fixed rate or one that varies in time, passed
+/// through a synapse into a current or a conductance.
+///
+/// Each step gives the synapse's output at its start, so a spike is felt from the step
+/// after the one it falls in, as with a synapse in a `Network`. `step` only
+/// serves current synapses and panics on a receptor one, whose conductance
+/// comes from `input`.
+///
+/// Spikes are placed by time rescaling: an exponential variate of integrated
+/// rate is drawn for each interval, with the rate taken as linear across a step,
+/// so spike times fall inside the step rather than on its edges.
+pub struct PoissonInput {
+    rate: Box<dyn Fn(Time) -> f64>,
+    synapse: Synapse,
+    // synapse output at the end of the last step
+    output: SynapticInput,
+    rng: Stream,
+    clock: Time,
+    // integrated rate left before the next spike
+    remaining: f64,
+    spikes: usize,
+}
+
+impl PoissonInput {
+    /// `rate` in Hz.
+    pub fn new(rate: f64, synapse: Synapse, rng: Stream) -> Self {
+        Self::inhomogeneous(move |_| rate, synapse, rng)
+    }
+
+    /// `rate` in Hz as a function of time since the start of the run;
+    /// negative values are treated as zero.
+    pub fn inhomogeneous(rate: impl Fn(Time) -> f64 + 'static, synapse: Synapse, mut rng: Stream) -> Self {
+        let remaining = Exp1.sample(&mut rng);
+        Self {
+            rate: Box::new(rate),
+            output: synapse.silent(),
+            synapse,
+            rng,
+            clock: Time::ZERO,
+            remaining,
+            spikes: 0,
+        }
+    }
+
+    /// Spikes generated so far.
+    pub fn spike_count(&self) -> usize {
+        self.spikes
+    }
+}
+
+impl SpikeGenerator for PoissonInput {
+    fn step(&mut self, dt: Time) -> Current {
+        self.input(dt).current()
+    }
+
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        let step = dt.seconds();
+        let r0 = (self.rate)(self.clock).max(0.0);
+        let r1 = (self.rate)(self.clock + dt).max(0.0);
+        // integrated rate from the start of the step to `s` seconds into it
+        let slope = (r1 - r0) / step;
+        let integral = |s: f64| r0 * s + 0.5 * slope * s * s;
+
+        let mut from = 0.0;
+        loop {
+            let target = integral(from) + self.remaining;
+            if target > integral(step) {
+                self.remaining = target - integral(step);
+                break;
+            }
+            // solve r0 s + slope s^2 / 2 = target for the root inside the step
+            let s = if slope.abs() < 1.0e-12 {
+                target / r0
+            } else {
+                (-r0 + (r0 * r0 + 2.0 * slope * target).max(0.0).sqrt()) / slope
+            }
+            .clamp(from, step);
+            self.synapse.fire(Time::from_seconds(s));
+            self.spikes += 1;
+            self.remaining = Exp1.sample(&mut self.rng);
+            from = s;
+        }
+        self.clock += dt;
+        std::mem::replace(&mut self.output, self.synapse.step(dt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neuron::random::Seed;
+    use crate::neuron::synapse::{Kinetics, Receptor};
+    use crate::neuron::units::Conductance;
+
+    const DT: f64 = 0.1;
+
+    fn kernel() -> Synapse {
+        Synapse::new(Current::from_picoamps(10.0), Time::from_millis(5.0))
+    }
+
+    fn run(input: &mut PoissonInput, seconds: f64) -> Vec<f64> {
+        (0..(seconds * 1.0e3 / DT).round() as usize)
+            .map(|_| input.step(Time::from_millis(DT)).picoamps())
+            .collect()
+    }
+
+    #[test]
+    fn count_matches_rate() {
+        // 20 Hz for 100 s, 2000 spikes with a standard deviation of about 45
+        let mut input = PoissonInput::new(20.0, kernel(), Seed::new(3).stream());
+        run(&mut input, 100.0);
+        assert!((input.spike_count() as f64 - 2000.0).abs() < 150.0, "{}", input.spike_count());
+    }
+
+    #[test]
+    fn high_rates_give_several_spikes_per_step() {
+        // 50 kHz is five spikes per 0.1 ms step on average
+        let mut input = PoissonInput::new(50.0e3, kernel(), Seed::new(4).stream());
+        run(&mut input, 1.0);
+        assert!((input.spike_count() as f64 - 50.0e3).abs() < 1000.0, "{}", input.spike_count());
+    }
+
+    #[test]
+    fn mean_current_follows_campbell() {
+        // mean = rate * amplitude * tau = 1000 Hz * 10 pA * 5 ms
+        let mut input = PoissonInput::new(1000.0, kernel(), Seed::new(5).stream());
+        let trace = run(&mut input, 20.0);
+        let mean = trace.iter().sum::<f64>() / trace.len() as f64;
+        assert!((mean - 50.0).abs() < 1.5, "{}", mean);
+    }
+
+    #[test]
+    fn other_kinetics_pass_through() {
+        // alpha PSCs carry e * tau of charge per unit peak: 1000 Hz * 10 pA * e * 2 ms
+        let synapse = Synapse::with_kinetics(Current::from_picoamps(10.0), Kinetics::Alpha { tau: Time::from_millis(2.0) });
+        let mut input = PoissonInput::new(1000.0, synapse, Seed::new(9).stream());
+        let trace = run(&mut input, 20.0);
+        let mean = trace.iter().sum::<f64>() / trace.len() as f64;
+        assert!((mean - 20.0 * std::f64::consts::E).abs() < 1.5, "{}", mean);
+    }
+
+    #[test]
+    fn inhomogeneous_rate_is_followed() {
+        // silent for 5 s, then a ramp from 0 to 100 Hz over the next 5 s
+        let rate = |t: Time| if t.seconds() < 5.0 { 0.0 } else { 20.0 * (t.seconds() - 5.0) };
+        let mut input = PoissonInput::inhomogeneous(rate, kernel(), Seed::new(6).stream());
+        run(&mut input, 5.0);
+        assert_eq!(input.spike_count(), 0);
+        run(&mut input, 5.0);
+        assert!((input.spike_count() as f64 - 250.0).abs() < 50.0, "{}", input.spike_count());
+    }
+
+    #[test]
+    fn spikes_are_felt_from_the_next_step() {
+        let mut input = PoissonInput::new(50.0e3, kernel(), Seed::new(8).stream());
+        assert_eq!(input.step(Time::from_millis(DT)), Current::ZERO);
+        assert!(input.spike_count() > 0);
+        assert!(input.step(Time::from_millis(DT)) > Current::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "conductance")]
+    fn receptor_synapses_need_input() {
+        let synapse = Synapse::conductance(Receptor::Ampa, Conductance::from_nanosiemens(1.0));
+        PoissonInput::new(100.0, synapse, Seed::new(10).stream()).step(Time::from_millis(DT));
+    }
+
+    #[test]
+    fn same_seed_same_train() {
+        let a = run(&mut PoissonInput::new(100.0, kernel(), Seed::new(7).stream()), 1.0);
+        let b = run(&mut PoissonInput::new(100.0, kernel(), Seed::new(7).stream()), 1.0);
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,177 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use crate::neuron::izhikevich::{IzhikevichConstants, IzhikevichParams};
+
+/// Named Izhikevich parameter sets.
+///
+/// Stored as a table of names to constants, so a TOML file reads
+///
+/// ```toml
+/// [tonic_spiking]
+/// a = 0.02
+/// b = 0.2
+/// c = -65.0
+/// d = 6.0
+/// threshold = 30.0
+/// v0 = -70.0
+/// u0 = -14.0
+/// ```
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IzhikevichPresets {
+    presets: BTreeMap<String, IzhikevichConstants>,
+}
+
+impl IzhikevichPresets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `IzhikevichParams` cell types, under their variant names.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for params in IzhikevichParams::iter() {
+            registry.insert(format!("{:?}", params), params.constants());
+        }
+        registry
+    }
+
+    pub fn get(&self, name: &str) -> Option<IzhikevichConstants> {
+        self.presets.get(name).copied()
+    }
+
+    /// Adds a preset, replacing any with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, constants: IzhikevichConstants) {
+        self.presets.insert(name.into(), constants);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<IzhikevichConstants> {
+        self.presets.remove(name)
+    }
+
+    /// Adds every preset in `other`, which wins on a name clash.
+    pub fn merge(&mut self, other: IzhikevichPresets) {
+        self.presets.extend(other.presets);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(text: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Reads a `.toml` or `.json` file, chosen by extension.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("reading presets from {}", path.display()))?;
+        match extension(path)? {
+            Format::Toml => Self::from_toml(&text),
+            Format::Json => Self::from_json(&text),
+        }
+        .with_context(|| format!("parsing presets in {}", path.display()))
+    }
+
+    /// Writes a `.toml` or `.json` file, chosen by extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let text = match extension(path)? {
+            Format::Toml => self.to_toml()?,
+            Format::Json => self.to_json()?,
+        };
+        fs::write(path, text).with_context(|| format!("writing presets to {}", path.display()))
+    }
+}
+
+enum Format {
+    Toml,
+    Json,
+}
+
+fn extension(path: &Path) -> anyhow::Result<Format> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => Ok(Format::Toml),
+        Some("json") => Ok(Format::Json),
+        _ => Err(anyhow!("{} is neither .toml nor .json", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_has_every_cell_type() {
+        let presets = IzhikevichPresets::builtin();
+        assert_eq!(presets.len(), 22);
+        assert_eq!(presets.get("tonic_spiking"), Some(IzhikevichParams::tonic_spiking.constants()));
+        assert_eq!(presets.get("accomodation").unwrap().u0, -16.0);
+    }
+
+    #[test]
+    fn round_trips_through_toml_and_json() {
+        let mut presets = IzhikevichPresets::builtin();
+        let mut tuned = presets.get("fast_spiking").unwrap();
+        tuned.d = 2.5;
+        presets.insert("lab_fs", tuned);
+        assert_eq!(IzhikevichPresets::from_toml(&presets.to_toml().unwrap()).unwrap(), presets);
+        assert_eq!(IzhikevichPresets::from_json(&presets.to_json().unwrap()).unwrap(), presets);
+    }
+
+    #[test]
+    fn loads_hand_written_toml() {
+        let presets = IzhikevichPresets::from_toml("
+            [slow]
+            a = 0.01
+            b = 0.2
+            c = -65.0
+            d = 8.0
+            threshold = 30.0
+            v0 = -65.0
+            u0 = -13.0
+        ").unwrap();
+        assert_eq!(presets.get("slow").unwrap().a, 0.01);
+        assert!(IzhikevichPresets::from_toml("[broken]\na = 0.01").is_err());
+    }
+
+    #[test]
+    fn save_and_load_by_extension() {
+        let dir = std::env::temp_dir();
+        let presets = IzhikevichPresets::builtin();
+        for name in ["neuron_presets_test.toml", "neuron_presets_test.json"] {
+            let path = dir.join(name);
+            presets.save(&path).unwrap();
+            assert_eq!(IzhikevichPresets::load(&path).unwrap(), presets);
+            fs::remove_file(path).unwrap();
+        }
+        assert!(presets.save(dir.join("neuron_presets_test.yaml")).is_err());
+    }
+}
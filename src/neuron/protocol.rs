@@ -0,0 +1,121 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::neuron::engine::NeuronEngine;
+use crate::neuron::integrator::{Integrator, SpikeInterpolation};
+use crate::neuron::izhikevich::{Izhikevich, IzhikevichConstants};
+use crate::neuron::network::Listeners;
+use crate::neuron::stimulus::Waveform;
+use crate::neuron::units::{Current, Time, Voltage};
+
+/// How one panel of Izhikevich (2004), "Which model to use for cortical
+/// spiking neurons?", is driven: the injected current as a function of time,
+/// the run length and the step, as in the MATLAB script behind the figure.
+#[derive(Clone, Copy)]
+pub struct Protocol {
+    /// Letter of the panel in the figure, `None` for cells that are not in it.
+    pub panel: Option<char>,
+    pub duration: Time,
+    pub dt: Time,
+    pub current: fn(Time) -> Current,
+}
+
+/// Potential and input current at the end of every step. As in the figure,
+/// the potential is drawn at threshold on the step a spike happens.
+#[derive(Debug, Default, Clone)]
+pub struct Trace {
+    pub time: Vec<Time>,
+    pub v: Vec<Voltage>,
+    pub current: Vec<Current>,
+    pub spikes: Vec<Time>,
+}
+
+impl Protocol {
+    pub fn steps(&self) -> usize {
+        (self.duration / self.dt).round() as usize
+    }
+
+    /// When the stimulus starts and stops: the first step at which the current
+    /// leaves its initial level, and the end of the last step before it
+    /// returns to it, or the end of the run if it never does.
+    pub fn window(&self) -> (Time, Time) {
+        let current: Vec<Current> = (0..self.steps()).map(|n| (self.current)(self.dt * n as f64)).collect();
+        let rest = current[0];
+        let start = current.iter().position(|&i| i != rest).unwrap_or(0);
+        let stop = match current.iter().rposition(|&i| i != rest) {
+            Some(n) if current[current.len() - 1] == rest => n + 1,
+            _ => current.len(),
+        };
+        (self.dt * start as f64, self.dt * stop as f64)
+    }
+
+    /// The current as a generator, to drive a node through a `Device`.
+    pub fn stimulus(&self) -> Waveform {
+        Waveform::new(Box::new(self.current))
+    }
+
+    /// Runs a cell with `constants` through the protocol, integrated with the
+    /// semi-implicit Euler scheme of the script.
+    pub fn run(&self, constants: IzhikevichConstants) -> Trace {
+        let mut model = Izhikevich::with_constants(constants);
+        model.set_integrator(Integrator::SemiImplicitEuler);
+        model.set_spike_interpolation(SpikeInterpolation::EndOfStep);
+        let listeners = Rc::new(RefCell::new(Listeners::new()));
+        let mut trace = Trace::default();
+        for n in 0..self.steps() {
+            let t = self.dt * n as f64;
+            model.receive((self.current)(t));
+            let (spike, i) = model.step(listeners.clone(), self.dt);
+            let v = if spike.is_some() {
+                trace.spikes.push(t + self.dt);
+                Voltage::from_millivolts(constants.threshold)
+            } else {
+                model.get_membrane_potential()
+            };
+            trace.time.push(t + self.dt);
+            trace.v.push(v);
+            trace.current.push(i);
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+    use crate::neuron::izhikevich::IzhikevichParams;
+    use crate::neuron::izhikevich::IzhikevichParams::*;
+
+    fn spike_times(params: IzhikevichParams) -> Vec<f64> {
+        params.protocol().run(params.constants()).spikes.iter().map(|t| t.millis()).collect()
+    }
+
+    #[test]
+    fn every_panel_has_one_protocol() {
+        let mut panels: Vec<char> = IzhikevichParams::iter().filter_map(|p| p.protocol().panel).collect();
+        panels.sort();
+        assert_eq!(panels, ('A'..='T').collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn phasic_spiking_fires_once_at_onset() {
+        assert_eq!(spike_times(phasic_spiking).len(), 1);
+    }
+
+    #[test]
+    fn rebound_spike_follows_inhibition() {
+        // a hyperpolarising pulse from 20 to 25 ms
+        let times = spike_times(rebound_spike);
+        assert_eq!(times.len(), 1);
+        assert!(times[0] > 25.0, "{:?}", times);
+    }
+
+    #[test]
+    fn accommodation_ignores_the_slow_ramp() {
+        // a ramp to 8 pA over 200 ms, then to 4 pA over 12.5 ms from 300 ms
+        let times = spike_times(accomodation);
+        assert_eq!(times.len(), 1);
+        assert!(times[0] > 300.0, "{:?}", times);
+    }
+}
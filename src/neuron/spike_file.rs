@@ -0,0 +1,206 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Context};
+use crate::neuron::engine::SpikeGenerator;
+use crate::neuron::synapse::{Synapse, SynapticInput};
+use crate::neuron::units::{Current, Time};
+
+/// Spike times per source neuron, as read from a spike file.
+///
+/// A file holds either one time per line, all taken as neuron 0, or
+/// `neuron_id time` pairs as NEST and Brian export them, separated by
+/// whitespace or commas. A header line, blank lines and lines starting with
+/// `#` are skipped.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SpikeTrains {
+    trains: BTreeMap<u64, Vec<Time>>,
+}
+
+impl SpikeTrains {
+    /// `unit` is the time one unit in the file stands for, e.g. 1 ms for NEST.
+    pub fn parse(text: &str, unit: Time) -> anyhow::Result<Self> {
+        let mut trains: BTreeMap<u64, Vec<Time>> = BTreeMap::new();
+        let mut columns = None;
+        let mut lines = text.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .peekable();
+        let mut first = true;
+        while let Some((n, line)) = lines.next() {
+            let header = std::mem::replace(&mut first, false);
+            let fields: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|f| !f.is_empty())
+                .collect();
+            let parsed = match fields[..] {
+                [time] => time.parse::<f64>().map(|t| (0, t)).map_err(|e| e.to_string()),
+                [id, time] => id.parse::<u64>().map_err(|e| e.to_string())
+                    .and_then(|id| time.parse::<f64>().map(|t| (id, t)).map_err(|e| e.to_string())),
+                _ => Err(format!("expected 1 or 2 fields, found {}", fields.len())),
+            };
+            let (id, time) = match parsed {
+                Ok(spike) => spike,
+                // the first line may be a header
+                Err(_) if header && lines.peek().is_some() => continue,
+                Err(e) => return Err(anyhow!("line {}: {}", n + 1, e)),
+            };
+            if !time.is_finite() {
+                return Err(anyhow!("line {}: spike time {} is not finite", n + 1, time));
+            }
+            if *columns.get_or_insert(fields.len()) != fields.len() {
+                return Err(anyhow!("line {}: mixes one and two column spikes", n + 1));
+            }
+            trains.entry(id).or_default().push(unit * time);
+        }
+        for train in trains.values_mut() {
+            train.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        }
+        Ok(Self { trains })
+    }
+
+    pub fn load(path: impl AsRef<Path>, unit: Time) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text, unit).with_context(|| format!("parsing spikes in {}", path.display()))
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.trains.keys().copied()
+    }
+
+    /// Spike times of neuron `id` in order, empty if it never fired.
+    pub fn train(&self, id: u64) -> &[Time] {
+        self.trains.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// A generator that plays the spikes of neuron `id` through `synapse`.
+    pub fn replay(&self, id: u64, synapse: Synapse) -> SpikeReplay {
+        SpikeReplay::new(self.train(id).to_vec(), synapse)
+    }
+}
+
+/// Replays a fixed list of spike times through a synapse. Each spike enters
+/// at its own time inside the step, not at the step boundary, and is felt
+/// from the next step: every step gives the synapse's output at its start.
+/// As with `PoissonInput`, `step` panics on a receptor synapse; use `input`.
+pub struct SpikeReplay {
+    times: Vec<Time>,
+    next: usize,
+    synapse: Synapse,
+    output: SynapticInput,
+    clock: Time,
+}
+
+impl SpikeReplay {
+    /// `times` must be sorted.
+    pub fn new(times: Vec<Time>, synapse: Synapse) -> Self {
+        Self { times, next: 0, output: synapse.silent(), synapse, clock: Time::ZERO }
+    }
+}
+
+impl SpikeGenerator for SpikeReplay {
+    fn step(&mut self, dt: Time) -> Current {
+        self.input(dt).current()
+    }
+
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        let end = self.clock + dt;
+        while self.next < self.times.len() && self.times[self.next] < end {
+            self.synapse.fire(self.times[self.next] - self.clock);
+            self.next += 1;
+        }
+        self.clock = end;
+        std::mem::replace(&mut self.output, self.synapse.step(dt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use super::*;
+    use crate::neuron::device::Device;
+    use crate::neuron::integrate_fire::IntegrateFire;
+    use crate::neuron::network::Network;
+    use crate::neuron::synapse::Receptor;
+    use crate::neuron::units::Conductance;
+
+    fn ms(x: f64) -> Time {
+        Time::from_millis(x)
+    }
+
+    #[test]
+    fn parses_single_column() {
+        let trains = SpikeTrains::parse("# spikes\n3.5\n1.0\n\n2.25\n", ms(1.0)).unwrap();
+        assert_eq!(trains.ids().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(trains.train(0), &[ms(1.0), ms(2.25), ms(3.5)]);
+    }
+
+    #[test]
+    fn parses_id_time_pairs() {
+        // Brian style, seconds, with a header
+        let trains = SpikeTrains::parse("i,t\n2,0.010\n0,0.005\n2,0.001\n", Time::from_seconds(1.0)).unwrap();
+        assert_eq!(trains.ids().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(trains.train(2), &[ms(1.0), ms(10.0)]);
+        assert!(trains.train(1).is_empty());
+        assert!(SpikeTrains::parse("1 1.0\n2.0\n", ms(1.0)).is_err());
+        assert!(SpikeTrains::parse("1 1.0\n2 x\n", ms(1.0)).is_err());
+    }
+
+    #[test]
+    fn only_one_header_line_is_skipped() {
+        assert!(SpikeTrains::parse("# NEST\ni t\n0 1.0\n", ms(1.0)).is_ok());
+        let err = SpikeTrains::parse("i t\ncorrupt\n0 1.0\n", ms(1.0)).unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"), "{}", err);
+    }
+
+    #[test]
+    fn non_finite_times_are_rejected() {
+        for time in ["nan", "inf", "-inf"] {
+            let err = SpikeTrains::parse(&format!("0 1.0\n0 {}\n", time), ms(1.0)).unwrap_err();
+            assert!(err.to_string().starts_with("line 2:"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn replay_places_spikes_inside_the_step() {
+        let kernel = Synapse::new(Current::from_picoamps(10.0), ms(2.0));
+        let mut replay = SpikeReplay::new(vec![ms(1.05)], kernel);
+        let trace: Vec<f64> = (0..13).map(|_| replay.step(ms(0.1)).picoamps()).collect();
+        // the spike falls in the step from 1.0 ms and is felt from 1.1 ms
+        assert!(trace[..11].iter().all(|&i| i == 0.0));
+        assert!((trace[11] - 10.0 * (-0.025f64).exp()).abs() < 1.0e-9);
+        assert!((trace[12] - 10.0 * (-0.075f64).exp()).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn replay_through_a_receptor() {
+        let synapse = Synapse::conductance(Receptor::Ampa, Conductance::from_nanosiemens(1.0));
+        let mut replay = SpikeReplay::new(vec![ms(0.05)], synapse);
+        assert_eq!(replay.input(ms(0.1)), SynapticInput::Conductance(Receptor::Ampa, Conductance::ZERO));
+        let SynapticInput::Conductance(receptor, g) = replay.input(ms(0.1)) else { panic!() };
+        assert_eq!(receptor, Receptor::Ampa);
+        assert!(g > Conductance::ZERO);
+    }
+
+    #[test]
+    fn replayed_spikes_drive_a_node() {
+        let trains = SpikeTrains::parse("0 5.0\n0 25.0\n1 15.0\n", ms(1.0)).unwrap();
+        let mut network = Network::new();
+        let cell = network.add_neuron(IntegrateFire::default());
+        let node = network.neuron_mut(cell).unwrap();
+        // 10 pC fires the default cell once per input, even soon after a reset
+        for id in trains.ids() {
+            let kernel = Synapse::new(Current::from_nanoamps(10.0), ms(1.0));
+            node.attach(&Device::new(Box::new(trains.replay(id, kernel))));
+        }
+        let fired = Rc::new(Cell::new(0));
+        let f = fired.clone();
+        node.listeners.borrow_mut().add(Box::new(move |_| f.set(f.get() + 1)));
+        for _ in 0..400 {
+            network.step(ms(0.1));
+        }
+        assert_eq!(fired.get(), 3);
+    }
+}
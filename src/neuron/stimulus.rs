@@ -0,0 +1,458 @@
+#![allow(dead_code)]
+
+use std::f64::consts::PI;
+use rand_distr::{Distribution, StandardNormal};
+use crate::neuron::engine::SpikeGenerator;
+use crate::neuron::random::Stream;
+use crate::neuron::synapse::SynapticInput;
+use crate::neuron::units::{Current, Time};
+
+// Every waveform is sampled at the start of the step, like `DCSG`, and keeps
+// its own clock so it can be shifted with `Delay`.
+
+/// Square pulses of `amplitude` lasting `width`, one every `period`.
+pub struct PulseTrain {
+    amplitude: Current,
+    width: Time,
+    period: Time,
+    counter: Time,
+}
+
+impl PulseTrain {
+    pub fn new(amplitude: Current, width: Time, period: Time) -> Self {
+        Self { amplitude, width, period, counter: Time::ZERO }
+    }
+}
+
+impl SpikeGenerator for PulseTrain {
+    fn step(&mut self, dt: Time) -> Current {
+        let phase = self.counter.seconds().rem_euclid(self.period.seconds());
+        let i = if phase < self.width.seconds() { self.amplitude } else { Current::ZERO };
+        self.counter += dt;
+        i
+    }
+}
+
+/// `offset + amplitude * sin(2 pi f t)`, with `frequency` in Hz.
+pub struct Sine {
+    amplitude: Current,
+    frequency: f64,
+    offset: Current,
+    counter: Time,
+}
+
+impl Sine {
+    pub fn new(amplitude: Current, frequency: f64, offset: Current) -> Self {
+        Self { amplitude, frequency, offset, counter: Time::ZERO }
+    }
+}
+
+impl SpikeGenerator for Sine {
+    fn step(&mut self, dt: Time) -> Current {
+        let i = self.offset + self.amplitude * (2.0 * PI * self.frequency * self.counter.seconds()).sin();
+        self.counter += dt;
+        i
+    }
+}
+
+/// ZAP current: a sine whose frequency sweeps linearly from `start` to `end` Hz
+/// over `duration`, then stops. Used to find the resonant frequency of a cell.
+pub struct Chirp {
+    amplitude: Current,
+    start: f64,
+    end: f64,
+    duration: Time,
+    counter: Time,
+}
+
+impl Chirp {
+    pub fn new(amplitude: Current, start: f64, end: f64, duration: Time) -> Self {
+        Self { amplitude, start, end, duration, counter: Time::ZERO }
+    }
+
+    /// Instantaneous frequency in Hz at `t` into the sweep.
+    pub fn frequency(&self, t: Time) -> f64 {
+        self.start + (self.end - self.start) * (t / self.duration)
+    }
+}
+
+impl SpikeGenerator for Chirp {
+    fn step(&mut self, dt: Time) -> Current {
+        let mut i = Current::ZERO;
+        if self.counter < self.duration {
+            let t = self.counter.seconds();
+            let sweep = (self.end - self.start) / self.duration.seconds();
+            i = self.amplitude * (2.0 * PI * (self.start * t + 0.5 * sweep * t * t)).sin();
+        }
+        self.counter += dt;
+        i
+    }
+}
+
+/// Holds each level for its duration in turn, then returns to zero.
+pub struct StepSequence {
+    steps: Vec<(Time, Current)>,
+    counter: Time,
+}
+
+impl StepSequence {
+    /// `steps` are `(duration, level)` pairs.
+    pub fn new(steps: Vec<(Time, Current)>) -> Self {
+        Self { steps, counter: Time::ZERO }
+    }
+}
+
+impl SpikeGenerator for StepSequence {
+    fn step(&mut self, dt: Time) -> Current {
+        let mut end = Time::ZERO;
+        let mut i = Current::ZERO;
+        for &(duration, level) in &self.steps {
+            end += duration;
+            if self.counter < end {
+                i = level;
+                break;
+            }
+        }
+        self.counter += dt;
+        i
+    }
+}
+
+/// Any current given as a function of the time since the first step. It is
+/// sampled at whole multiples of `dt` rather than a running sum, so edges
+/// written as `t > 10 ms` fall on the same step however long the run; this
+/// assumes a fixed step.
+pub struct Waveform {
+    current: Box<dyn Fn(Time) -> Current>,
+    steps: u64,
+}
+
+impl Waveform {
+    pub fn new(current: Box<dyn Fn(Time) -> Current>) -> Self {
+        Self { current, steps: 0 }
+    }
+}
+
+impl SpikeGenerator for Waveform {
+    fn step(&mut self, dt: Time) -> Current {
+        let i = (self.current)(dt * self.steps as f64);
+        self.steps += 1;
+        i
+    }
+}
+
+/// Constant current with independent Gaussian fluctuations drawn every step.
+pub struct NoisyDC {
+    mean: Current,
+    sigma: Current,
+    rng: Stream,
+}
+
+impl NoisyDC {
+    pub fn new(mean: Current, sigma: Current, rng: Stream) -> Self {
+        Self { mean, sigma, rng }
+    }
+}
+
+impl SpikeGenerator for NoisyDC {
+    fn step(&mut self, _dt: Time) -> Current {
+        let z: f64 = StandardNormal.sample(&mut self.rng);
+        self.mean + self.sigma * z
+    }
+}
+
+/// Ornstein-Uhlenbeck current, noise with mean `mean`, stationary standard
+/// deviation `sigma` and correlation time `tau`. Advanced with the exact
+/// update, so its statistics do not depend on the step size.
+pub struct OrnsteinUhlenbeck {
+    mean: Current,
+    sigma: Current,
+    tau: Time,
+    current: Current,
+    rng: Stream,
+}
+
+impl OrnsteinUhlenbeck {
+    /// Starts from a draw of the stationary distribution, so there is no transient.
+    pub fn new(mean: Current, sigma: Current, tau: Time, mut rng: Stream) -> Self {
+        let z: f64 = StandardNormal.sample(&mut rng);
+        Self { mean, sigma, tau, current: mean + sigma * z, rng }
+    }
+}
+
+impl SpikeGenerator for OrnsteinUhlenbeck {
+    fn step(&mut self, dt: Time) -> Current {
+        let i = self.current;
+        let decay = (-(dt / self.tau)).exp();
+        let z: f64 = StandardNormal.sample(&mut self.rng);
+        self.current = self.mean + (self.current - self.mean) * decay
+            + self.sigma * ((1.0 - decay * decay).sqrt() * z);
+        i
+    }
+}
+
+/// Adds the output of every generator. Their inputs must be all currents or
+/// all conductances on one receptor; `input` panics on a mix.
+pub struct Sum {
+    generators: Vec<Box<dyn SpikeGenerator>>,
+}
+
+impl Sum {
+    pub fn new(generators: Vec<Box<dyn SpikeGenerator>>) -> Self {
+        Self { generators }
+    }
+}
+
+impl SpikeGenerator for Sum {
+    fn step(&mut self, dt: Time) -> Current {
+        self.generators.iter_mut().map(|g| g.step(dt)).sum()
+    }
+
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        self.generators.iter_mut().map(|g| g.input(dt))
+            .try_fold(SynapticInput::Current(Current::ZERO), |sum, input| sum.plus(input).ok_or(input))
+            .unwrap_or_else(|input| panic!("Sum cannot add {:?} to the other inputs", input))
+    }
+}
+
+/// Holds `inner` at zero, without advancing it, for the first `delay`.
+pub struct Delay {
+    inner: Box<dyn SpikeGenerator>,
+    delay: Time,
+    counter: Time,
+}
+
+impl Delay {
+    pub fn new(inner: Box<dyn SpikeGenerator>, delay: Time) -> Self {
+        Self { inner, delay, counter: Time::ZERO }
+    }
+}
+
+impl SpikeGenerator for Delay {
+    fn step(&mut self, dt: Time) -> Current {
+        if self.counter < self.delay {
+            self.counter += dt;
+            return Current::ZERO;
+        }
+        self.inner.step(dt)
+    }
+
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        if self.counter < self.delay {
+            self.counter += dt;
+            return SynapticInput::Current(Current::ZERO);
+        }
+        self.inner.input(dt)
+    }
+}
+
+/// Passes `inner` through between `start` and `stop` only. Unlike `Delay`,
+/// `inner` keeps running while the gate is shut.
+pub struct Gate {
+    inner: Box<dyn SpikeGenerator>,
+    start: Time,
+    stop: Time,
+    counter: Time,
+}
+
+impl Gate {
+    pub fn new(inner: Box<dyn SpikeGenerator>, start: Time, stop: Time) -> Self {
+        Self { inner, start, stop, counter: Time::ZERO }
+    }
+}
+
+impl SpikeGenerator for Gate {
+    fn step(&mut self, dt: Time) -> Current {
+        let i = self.inner.step(dt);
+        let open = self.counter >= self.start && self.counter < self.stop;
+        self.counter += dt;
+        if open { i } else { Current::ZERO }
+    }
+
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        let input = self.inner.input(dt);
+        let open = self.counter >= self.start && self.counter < self.stop;
+        self.counter += dt;
+        if open { input } else { input.scaled(0.0) }
+    }
+}
+
+/// Multiplies the output of `inner` by `factor`.
+pub struct Scale {
+    inner: Box<dyn SpikeGenerator>,
+    factor: f64,
+}
+
+impl Scale {
+    pub fn new(inner: Box<dyn SpikeGenerator>, factor: f64) -> Self {
+        Self { inner, factor }
+    }
+}
+
+impl SpikeGenerator for Scale {
+    fn step(&mut self, dt: Time) -> Current {
+        self.inner.step(dt) * self.factor
+    }
+
+    fn input(&mut self, dt: Time) -> SynapticInput {
+        self.inner.input(dt).scaled(self.factor)
+    }
+}
+
+/// Chains the combinators onto any generator, e.g.
+/// `PulseTrain::new(..).delayed(t).plus(NoisyDC::new(..)).boxed()`.
+pub trait Stimulus: SpikeGenerator + Sized + 'static {
+    fn boxed(self) -> Box<dyn SpikeGenerator> {
+        Box::new(self)
+    }
+
+    fn plus(self, other: impl SpikeGenerator + 'static) -> Sum {
+        Sum::new(vec![self.boxed(), Box::new(other)])
+    }
+
+    fn delayed(self, delay: Time) -> Delay {
+        Delay::new(self.boxed(), delay)
+    }
+
+    fn gated(self, start: Time, stop: Time) -> Gate {
+        Gate::new(self.boxed(), start, stop)
+    }
+
+    fn scaled(self, factor: f64) -> Scale {
+        Scale::new(self.boxed(), factor)
+    }
+}
+
+impl<G: SpikeGenerator + 'static> Stimulus for G {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neuron::engine::{DCSG, RampGenerator};
+    use crate::neuron::poisson::PoissonInput;
+    use crate::neuron::random::Seed;
+    use crate::neuron::synapse::{Receptor, Synapse};
+    use crate::neuron::units::Conductance;
+
+    const DT: f64 = 0.1;
+
+    // output in pA for each step of `DT` ms
+    fn trace(mut g: impl SpikeGenerator, duration: f64) -> Vec<f64> {
+        (0..(duration / DT).round() as usize).map(|_| g.step(Time::from_millis(DT)).picoamps()).collect()
+    }
+
+    fn pa(x: f64) -> Current {
+        Current::from_picoamps(x)
+    }
+
+    fn ms(x: f64) -> Time {
+        Time::from_millis(x)
+    }
+
+    #[test]
+    fn ramp_rises_then_holds() {
+        let t = trace(RampGenerator::new(pa(100.0), ms(10.0)), 20.0);
+        assert_eq!(t[0], 0.0);
+        assert!((t[50] - 50.0).abs() < 1.0e-9);
+        assert!((t[150] - 100.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn pulse_train_duty_cycle() {
+        // 2 ms on in every 10 ms
+        let t = trace(PulseTrain::new(pa(50.0), ms(2.0), ms(10.0)), 100.0);
+        assert!((t.iter().filter(|&&i| i == 50.0).count() as i64 - 200).abs() <= 10);
+        assert_eq!((t[5], t[25], t[105]), (50.0, 0.0, 50.0));
+    }
+
+    #[test]
+    fn sine_and_chirp_frequencies() {
+        // upward zero crossings of a 100 Hz sine over 105 ms, one per period after the first
+        let crossings = |t: &[f64]| t.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        let sine = trace(Sine::new(pa(10.0), 100.0, Current::ZERO), 105.0);
+        assert_eq!(crossings(&sine), 10);
+        // a 0 to 100 Hz sweep over 1 s averages 50 Hz
+        let chirp = trace(Chirp::new(pa(10.0), 0.0, 100.0, Time::from_seconds(1.0)), 1100.0);
+        assert!((crossings(&chirp) as i64 - 50).abs() <= 1);
+        assert!(chirp[10500..].iter().all(|&i| i == 0.0));
+    }
+
+    #[test]
+    fn step_sequence_levels() {
+        let t = trace(StepSequence::new(vec![(ms(5.0), pa(-20.0)), (ms(5.0), pa(40.0))]), 20.0);
+        assert_eq!((t[0], t[45], t[55], t[95], t[105]), (-20.0, -20.0, 40.0, 40.0, 0.0));
+    }
+
+    #[test]
+    fn waveform_edges_do_not_drift() {
+        let t = trace(Waveform::new(Box::new(|t| pa(if t > ms(10.0) { 1.0 } else { 0.0 }))), 20.0);
+        assert_eq!((t[100], t[101]), (0.0, 1.0));
+    }
+
+    #[test]
+    fn noisy_dc_statistics() {
+        let t = trace(NoisyDC::new(pa(100.0), pa(10.0), Seed::new(1).stream()), 10000.0);
+        let mean = t.iter().sum::<f64>() / t.len() as f64;
+        let sd = (t.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / t.len() as f64).sqrt();
+        assert!((mean - 100.0).abs() < 0.5, "{}", mean);
+        assert!((sd - 10.0).abs() < 0.5, "{}", sd);
+    }
+
+    #[test]
+    fn ornstein_uhlenbeck_is_independent_of_dt() {
+        for dt in [0.1, 1.0] {
+            let mut ou = OrnsteinUhlenbeck::new(pa(50.0), pa(20.0), ms(5.0), Seed::new(2).stream());
+            let t: Vec<f64> = (0..(100_000.0 / dt) as usize).map(|_| ou.step(ms(dt)).picoamps()).collect();
+            let mean = t.iter().sum::<f64>() / t.len() as f64;
+            let var = t.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / t.len() as f64;
+            assert!((mean - 50.0).abs() < 1.0, "dt {} mean {}", dt, mean);
+            assert!((var.sqrt() - 20.0).abs() < 1.0, "dt {} sd {}", dt, var.sqrt());
+            // autocorrelation one correlation time apart is 1/e
+            let lag = (5.0 / dt) as usize;
+            let cov = t.iter().zip(&t[lag..]).map(|(a, b)| (a - mean) * (b - mean)).sum::<f64>()
+                / (t.len() - lag) as f64;
+            assert!((cov / var - (-1.0f64).exp()).abs() < 0.05, "dt {} r {}", dt, cov / var);
+        }
+    }
+
+    #[test]
+    fn combinators_compose() {
+        let pulse = PulseTrain::new(pa(10.0), ms(1.0), ms(5.0));
+        let t = trace(pulse.delayed(ms(2.0)).scaled(2.0).plus(DCSG::new(pa(1.0))), 10.0);
+        assert_eq!((t[0], t[15], t[25], t[35], t[75]), (0.0, 1.0, 21.0, 1.0, 21.0));
+
+        // pulses at 0, 5 and 10 ms, of which only the second is let through
+        let gated = trace(PulseTrain::new(pa(10.0), ms(1.0), ms(5.0)).gated(ms(4.0), ms(7.0)), 15.0);
+        assert_eq!((gated[5], gated[55], gated[65], gated[105]), (0.0, 10.0, 0.0, 0.0));
+    }
+
+    fn ampa(seed: u64) -> PoissonInput {
+        let synapse = Synapse::conductance(Receptor::Ampa, Conductance::from_nanosiemens(1.0));
+        PoissonInput::new(1000.0, synapse, Seed::new(seed).stream())
+    }
+
+    #[test]
+    fn conductances_pass_through_combinators() {
+        // twice a train plus itself again, and one that has not started yet
+        let mut reference = ampa(11);
+        let mut combined = ampa(11).scaled(2.0).plus(ampa(11)).plus(ampa(12).delayed(ms(1.0e6)));
+        let mut open = 0;
+        for _ in 0..1000 {
+            let SynapticInput::Conductance(Receptor::Ampa, g) = reference.input(ms(DT)) else { panic!() };
+            let SynapticInput::Conductance(Receptor::Ampa, sum) = combined.input(ms(DT)) else { panic!() };
+            assert!((sum.nanosiemens() - 3.0 * g.nanosiemens()).abs() < 1.0e-9);
+            open += (g > Conductance::ZERO) as usize;
+        }
+        assert!(open > 500, "{}", open);
+    }
+
+    #[test]
+    #[should_panic(expected = "Sum cannot add")]
+    fn currents_and_conductances_do_not_mix() {
+        // DCSG is silent for its first step
+        let mut sum = ampa(13).plus(DCSG::new(pa(10.0)));
+        for _ in 0..2 {
+            sum.input(ms(DT));
+        }
+    }
+}
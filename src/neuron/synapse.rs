@@ -0,0 +1,429 @@
+#![allow(dead_code)]
+
+use crate::neuron::engine::NeuronEngine;
+use crate::neuron::units::{Capacitance, Conductance, Current, Time, Voltage};
+
+// extracellular magnesium, mM
+const MAGNESIUM: f64 = 1.0;
+
+/// Time course of the postsynaptic current that one spike produces.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Kinetics {
+    /// The whole PSC within the step the spike arrives in, carrying the
+    /// charge of the amplitude held for `width`.
+    Delta { width: Time },
+    /// Jumps to the amplitude and decays, `A exp(-t / tau)`.
+    Exponential { tau: Time },
+    /// Rises and falls with one time constant, `A (t / tau) exp(1 - t / tau)`,
+    /// peaking at the amplitude at `t = tau`.
+    Alpha { tau: Time },
+    /// Difference of exponentials, `exp(-t / decay) - exp(-t / rise)`, scaled to
+    /// peak at the amplitude. `rise` must be shorter than `decay`.
+    DualExponential { rise: Time, decay: Time },
+}
+
+/// Receptor port of a postsynaptic cell, each with its own reversal potential.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Receptor {
+    /// Fast excitation.
+    Ampa,
+    /// Fast inhibition, reversing near rest so it mostly shunts.
+    GabaA,
+    /// Slow inhibition through potassium channels.
+    GabaB,
+    /// Slow excitation, blocked by magnesium near rest.
+    Nmda,
+}
+
+impl Receptor {
+    pub const ALL: [Receptor; 4] = [Receptor::Ampa, Receptor::GabaA, Receptor::GabaB, Receptor::Nmda];
+
+    pub fn reversal_potential(&self) -> Voltage {
+        Voltage::from_millivolts(match self {
+            Receptor::Ampa | Receptor::Nmda => 0.0,
+            Receptor::GabaA => -70.0,
+            Receptor::GabaB => -95.0,
+        })
+    }
+
+    /// Typical conductance time course.
+    pub fn kinetics(&self) -> Kinetics {
+        let (rise, decay) = match self {
+            Receptor::Ampa => (0.2, 2.0),
+            Receptor::GabaA => (0.5, 8.0),
+            Receptor::GabaB => (50.0, 150.0),
+            Receptor::Nmda => (2.0, 100.0),
+        };
+        Kinetics::DualExponential { rise: Time::from_millis(rise), decay: Time::from_millis(decay) }
+    }
+
+    /// Fraction of the conductance left open at `v`: the magnesium block of
+    /// Jahr & Stevens (1990) for NMDA, and all of it for the others.
+    pub fn unblocked(&self, v: Voltage) -> f64 {
+        match self {
+            Receptor::Nmda => 1.0 / (1.0 + MAGNESIUM / 3.57 * (-0.062 * v.millivolts()).exp()),
+            _ => 1.0,
+        }
+    }
+
+    /// Current through conductance `g` into a cell at `v`, inward positive.
+    pub fn current(&self, g: Conductance, v: Voltage) -> Current {
+        g * (self.reversal_potential() - v) * self.unblocked(v)
+    }
+}
+
+/// Conductances open on each receptor port of a cell, gathered over a step.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Ports {
+    open: [Conductance; 4],
+}
+
+impl Ports {
+    pub fn open(&mut self, receptor: Receptor, g: Conductance) {
+        self.open[receptor as usize] += g;
+    }
+
+    pub fn close(&mut self) {
+        self.open = Default::default();
+    }
+
+    /// Carries a membrane at `v` with `capacitance` through the open
+    /// conductances for `dt` and closes them, returning the new potential and
+    /// the mean current they drove. With the conductances and NMDA block held
+    /// over the step this is an exact relaxation towards their weighted
+    /// reversal potential, so `v` never passes it however large they are.
+    pub fn relax(&mut self, v: Voltage, dt: Time, capacitance: Capacitance) -> (Voltage, Current) {
+        let mut g = Conductance::ZERO;
+        // sum of g * E, the current that would flow at 0 mV
+        let mut drive = Current::ZERO;
+        for receptor in Receptor::ALL {
+            let open = self.open[receptor as usize] * receptor.unblocked(v);
+            g += open;
+            drive += open * receptor.reversal_potential();
+        }
+        self.close();
+        if g == Conductance::ZERO {
+            return (v, Current::ZERO);
+        }
+        let reversal = drive / g;
+        let relaxed = reversal + (v - reversal) * (-(dt / (capacitance / g))).exp();
+        let current = Current::from_amps(capacitance.farads() * (relaxed - v).volts() / dt.seconds());
+        (relaxed, current)
+    }
+}
+
+/// What a synapse puts out each step.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SynapticInput {
+    Current(Current),
+    Conductance(Receptor, Conductance),
+}
+
+impl SynapticInput {
+    /// The injected current. A conductance has none until it meets a
+    /// membrane, so this panics on one; pass it on with `deliver`.
+    pub fn current(self) -> Current {
+        match self {
+            SynapticInput::Current(i) => i,
+            SynapticInput::Conductance(receptor, _) => {
+                panic!("a {:?} conductance drives no current of its own; deliver it to an engine", receptor)
+            }
+        }
+    }
+
+    /// The same current or conductance times `factor`.
+    pub fn scaled(self, factor: f64) -> Self {
+        match self {
+            SynapticInput::Current(i) => SynapticInput::Current(i * factor),
+            SynapticInput::Conductance(receptor, g) => SynapticInput::Conductance(receptor, g * factor),
+        }
+    }
+
+    /// The sum of two inputs, or `None` for a current and a conductance, or
+    /// conductances on different receptors. A zero current, as a silent or
+    /// not yet started source gives, adds to anything.
+    pub fn plus(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (SynapticInput::Current(a), SynapticInput::Current(b)) => Some(SynapticInput::Current(a + b)),
+            (SynapticInput::Current(Current::ZERO), input) | (input, SynapticInput::Current(Current::ZERO)) => Some(input),
+            (SynapticInput::Conductance(r, a), SynapticInput::Conductance(q, b)) if r == q => {
+                Some(SynapticInput::Conductance(r, a + b))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn deliver<E: NeuronEngine + ?Sized>(self, engine: &mut E) {
+        match self {
+            SynapticInput::Current(i) => engine.receive(i),
+            SynapticInput::Conductance(receptor, g) => engine.receive_conductance(receptor, g),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Amplitude {
+    Current(Current),
+    Conductance(Receptor, Conductance),
+}
+
+/// A synapse that injects a current, or opens a conductance on one of the
+/// target's receptors. The PSCs or conductances of overlapping spikes sum,
+/// and each is integrated exactly, so the result does not depend on the step
+/// size.
+pub struct Synapse {
+    amplitude: Amplitude,
+    kinetics: Kinetics,
+    // spikes to add at the end of this step, as offsets into it
+    pending: Vec<Time>,
+    // decaying terms of the summed PSCs; their meaning depends on the kinetics
+    x: f64,
+    y: f64,
+}
+
+impl Synapse {
+    /// Exponentially decaying PSC peaking at `max_current`, with time
+    /// constant `time_factor`.
+    pub fn new(max_current: Current, time_factor: Time) -> Self {
+        Self::with_kinetics(max_current, Kinetics::Exponential { tau: time_factor })
+    }
+
+    pub fn with_kinetics(amplitude: Current, kinetics: Kinetics) -> Self {
+        Self::build(Amplitude::Current(amplitude), kinetics)
+    }
+
+    /// Conductance on `receptor` peaking at `peak`, with the receptor's usual kinetics.
+    pub fn conductance(receptor: Receptor, peak: Conductance) -> Self {
+        Self::build(Amplitude::Conductance(receptor, peak), receptor.kinetics())
+    }
+
+    fn build(amplitude: Amplitude, kinetics: Kinetics) -> Self {
+        Self {
+            amplitude,
+            kinetics,
+            pending: Vec::new(),
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    pub fn kinetics(&self) -> Kinetics {
+        self.kinetics
+    }
+
+    /// `offset` is how far into the current step the presynaptic spike happened,
+    /// so the PSC is already `dt - offset` old when this step ends.
+    pub fn fire(&mut self, offset: Time) {
+        self.pending.push(offset);
+    }
+
+    /// Advances by `dt`, returning the current or conductance at the end of
+    /// the step, or the mean over it for delta kinetics.
+    pub fn step(&mut self, dt: Time) -> SynapticInput {
+        let scale = self.advance(dt);
+        self.output(scale)
+    }
+
+    /// Output with no PSC in flight, a zero current or conductance.
+    pub fn silent(&self) -> SynapticInput {
+        self.output(0.0)
+    }
+
+    fn output(&self, scale: f64) -> SynapticInput {
+        match self.amplitude {
+            Amplitude::Current(i) => SynapticInput::Current(i * scale),
+            Amplitude::Conductance(receptor, g) => SynapticInput::Conductance(receptor, g * scale),
+        }
+    }
+
+    // summed PSC shapes, each of unit peak
+    fn advance(&mut self, dt: Time) -> f64 {
+        let ages: Vec<Time> = self.pending.drain(..).map(|offset| dt - offset).collect();
+        match self.kinetics {
+            Kinetics::Delta { width } => (width / dt) * ages.len() as f64,
+            Kinetics::Exponential { tau } => {
+                self.x *= (-(dt / tau)).exp();
+                for age in ages {
+                    self.x += (-(age / tau)).exp();
+                }
+                self.x
+            }
+            Kinetics::Alpha { tau } => {
+                // x sums exp(-t / tau) and y sums t exp(-t / tau), t in seconds
+                let decay = (-(dt / tau)).exp();
+                self.y = (self.y + dt.seconds() * self.x) * decay;
+                self.x *= decay;
+                for age in ages {
+                    let e = (-(age / tau)).exp();
+                    self.x += e;
+                    self.y += age.seconds() * e;
+                }
+                std::f64::consts::E * self.y / tau.seconds()
+            }
+            Kinetics::DualExponential { rise, decay } => {
+                // x sums exp(-t / decay) and y sums exp(-t / rise)
+                self.x *= (-(dt / decay)).exp();
+                self.y *= (-(dt / rise)).exp();
+                for age in ages {
+                    self.x += (-(age / decay)).exp();
+                    self.y += (-(age / rise)).exp();
+                }
+                (self.x - self.y) / dual_exponential_peak(rise, decay)
+            }
+        }
+    }
+}
+
+/// Height of `exp(-t / decay) - exp(-t / rise)` at its maximum.
+fn dual_exponential_peak(rise: Time, decay: Time) -> f64 {
+    let peak = rise * (decay / (decay - rise)) * (decay / rise).ln();
+    (-(peak / decay)).exp() - (-(peak / rise)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f64 = 0.1;
+
+    fn ms(x: f64) -> Time {
+        Time::from_millis(x)
+    }
+
+    // currents at the end of each step, for spikes given as (step, offset in ms)
+    fn response(kinetics: Kinetics, spikes: &[(usize, f64)], steps: usize) -> Vec<Current> {
+        let mut synapse = Synapse::with_kinetics(Current::from_nanoamps(1.0), kinetics);
+        (0..steps).map(|n| {
+            for &(_, offset) in spikes.iter().filter(|&&(s, _)| s == n) {
+                synapse.fire(ms(offset));
+            }
+            match synapse.step(ms(DT)) {
+                SynapticInput::Current(i) => i,
+                input => panic!("{:?}", input),
+            }
+        }).collect()
+    }
+
+    // compares with `shape`, in nA, at the end of every step for a spike 0.03 ms in
+    fn check(kinetics: Kinetics, shape: impl Fn(f64) -> f64) {
+        let psc = response(kinetics, &[(0, 0.03)], 500);
+        for (n, i) in psc.iter().enumerate() {
+            let t = (n + 1) as f64 * DT - 0.03;
+            assert!((i.nanoamps() - shape(t)).abs() < 1.0e-9, "{:?} at {} ms: {} vs {}", kinetics, t, i, shape(t));
+        }
+    }
+
+    #[test]
+    fn exponential_decays_from_the_amplitude() {
+        check(Kinetics::Exponential { tau: ms(3.0) }, |t| (-t / 3.0).exp());
+    }
+
+    #[test]
+    fn alpha_peaks_at_tau() {
+        check(Kinetics::Alpha { tau: ms(2.0) }, |t| t / 2.0 * (1.0 - t / 2.0).exp());
+        let psc = response(Kinetics::Alpha { tau: ms(2.0) }, &[(0, 0.0)], 100);
+        let peak = psc.iter().copied().fold(Current::ZERO, |a, b| if b > a { b } else { a });
+        assert!((peak.nanoamps() - 1.0).abs() < 1.0e-9);
+        assert_eq!(psc.iter().position(|&i| i == peak), Some(19));
+    }
+
+    #[test]
+    fn dual_exponential_peaks_at_the_amplitude() {
+        let (rise, decay): (f64, f64) = (0.5, 5.0);
+        let peak = rise * decay / (decay - rise) * (decay / rise).ln();
+        let norm = (-peak / decay).exp() - (-peak / rise).exp();
+        check(Kinetics::DualExponential { rise: ms(rise), decay: ms(decay) },
+              |t| ((-t / decay).exp() - (-t / rise).exp()) / norm);
+        let psc = response(Kinetics::DualExponential { rise: ms(rise), decay: ms(decay) }, &[(0, 0.0)], 100);
+        let highest = psc.iter().map(|i| i.nanoamps()).fold(0.0, f64::max);
+        assert!(highest <= 1.0 + 1.0e-12 && highest > 0.99, "{}", highest);
+    }
+
+    #[test]
+    fn delta_delivers_its_charge_in_one_step() {
+        let psc = response(Kinetics::Delta { width: ms(0.5) }, &[(3, 0.04)], 10);
+        let charge: f64 = psc.iter().map(|i| i.nanoamps() * DT).sum();
+        assert!((charge - 0.5).abs() < 1.0e-12);
+        assert_eq!(psc.iter().filter(|&&i| i != Current::ZERO).count(), 1);
+    }
+
+    #[test]
+    fn overlapping_pscs_sum() {
+        let kinds = [
+            Kinetics::Delta { width: ms(0.1) },
+            Kinetics::Exponential { tau: ms(3.0) },
+            Kinetics::Alpha { tau: ms(2.0) },
+            Kinetics::DualExponential { rise: ms(0.5), decay: ms(5.0) },
+        ];
+        for kinetics in kinds {
+            let first = response(kinetics, &[(2, 0.01)], 300);
+            let second = response(kinetics, &[(2, 0.07), (40, 0.02)], 300);
+            let both = response(kinetics, &[(2, 0.01), (2, 0.07), (40, 0.02)], 300);
+            for n in 0..300 {
+                let sum = first[n] + second[n];
+                assert!((both[n] - sum).abs().nanoamps() < 1.0e-12, "{:?} step {}", kinetics, n);
+            }
+        }
+    }
+
+    fn mv(x: f64) -> Voltage {
+        Voltage::from_millivolts(x)
+    }
+
+    #[test]
+    fn receptor_currents_reverse() {
+        let g = Conductance::from_nanosiemens(1.0);
+        for receptor in [Receptor::Ampa, Receptor::GabaA, Receptor::GabaB, Receptor::Nmda] {
+            let e = receptor.reversal_potential();
+            assert_eq!(receptor.current(g, e), Current::ZERO);
+            assert!(receptor.current(g, e - mv(10.0)) > Current::ZERO, "{:?}", receptor);
+            assert!(receptor.current(g, e + mv(10.0)) < Current::ZERO, "{:?}", receptor);
+        }
+        // 1 nS at 70 mV from reversal
+        assert!((Receptor::Ampa.current(g, mv(-70.0)).picoamps() - 70.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn nmda_block_lifts_with_depolarisation() {
+        let block = |v| Receptor::Nmda.unblocked(mv(v));
+        assert!((block(0.0) - 3.57 / 4.57).abs() < 1.0e-12);
+        assert!(block(-80.0) < 0.05);
+        assert!(block(-80.0) < block(-40.0) && block(-40.0) < block(0.0) && block(0.0) < block(40.0));
+        assert_eq!(Receptor::Ampa.unblocked(mv(-80.0)), 1.0);
+    }
+
+    #[test]
+    fn ports_relax_towards_reversal() {
+        let c = Capacitance::from_picofarads(100.0);
+        let mut ports = Ports::default();
+        assert_eq!(ports.relax(mv(-60.0), ms(0.1), c), (mv(-60.0), Current::ZERO));
+
+        // 10 nS over 100 pF is a 10 ms time constant
+        ports.open(Receptor::GabaB, Conductance::from_nanosiemens(10.0));
+        let (v, i) = ports.relax(mv(-60.0), ms(1.0), c);
+        assert!((v.millivolts() - (-95.0 + 35.0 * (-0.1_f64).exp())).abs() < 1.0e-9);
+        assert!((i.picoamps() - 100.0 * (v.millivolts() + 60.0)).abs() < 1.0e-6);
+        // closed after the step
+        assert_eq!(ports.relax(v, ms(1.0), c).0, v);
+
+        // no overshoot however stiff, and equal conductances meet halfway
+        ports.open(Receptor::GabaA, Conductance::from_microsiemens(1.0));
+        ports.open(Receptor::Ampa, Conductance::from_microsiemens(1.0));
+        assert!((ports.relax(mv(-60.0), ms(1.0), c).0.millivolts() - -35.0).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn conductance_synapses_scale_the_shape() {
+        let g = Conductance::from_nanosiemens(2.0);
+        let mut synapse = Synapse::conductance(Receptor::GabaA, g);
+        let mut reference = Synapse::with_kinetics(Current::from_nanoamps(2.0), Receptor::GabaA.kinetics());
+        synapse.fire(ms(0.02));
+        reference.fire(ms(0.02));
+        for _ in 0..200 {
+            let (SynapticInput::Conductance(receptor, g), SynapticInput::Current(i)) =
+                (synapse.step(ms(DT)), reference.step(ms(DT))) else { panic!() };
+            assert_eq!(receptor, Receptor::GabaA);
+            assert!((g.nanosiemens() - i.nanoamps()).abs() < 1.0e-12);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+#![allow(dead_code)]
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use serde::Serialize;
+
+// A physical quantity stored in SI base units. Values only enter or leave
+// through constructors and accessors that name their scale, so a millivolt
+// can never be read as a volt.
+macro_rules! quantity {
+    ($name:ident, $symbol:literal) => {
+        #[derive(Debug, Default, PartialEq, PartialOrd, Copy, Clone, Serialize)]
+        pub struct $name(f64);
+
+        impl $name {
+            pub const ZERO: $name = $name(0.0);
+
+            pub fn abs(self) -> Self {
+                $name(self.0.abs())
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $name(-self.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: $name) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: $name) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = $name;
+            fn mul(self, rhs: f64) -> $name {
+                $name(self.0 * rhs)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+            fn mul(self, rhs: $name) -> $name {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = $name;
+            fn div(self, rhs: f64) -> $name {
+                $name(self.0 / rhs)
+            }
+        }
+
+        /// The dimensionless ratio of two quantities of the same kind.
+        impl Div for $name {
+            type Output = f64;
+            fn div(self, rhs: $name) -> f64 {
+                self.0 / rhs.0
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> $name {
+                $name(iter.map(|q| q.0).sum())
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)?;
+                write!(f, " {}", $symbol)
+            }
+        }
+    };
+}
+
+// named constructor and accessor for one scale of a quantity
+macro_rules! scale {
+    ($name:ident, $from:ident, $to:ident, $factor:literal) => {
+        impl $name {
+            pub fn $from(value: f64) -> Self {
+                $name(value * $factor)
+            }
+
+            pub fn $to(&self) -> f64 {
+                self.0 / $factor
+            }
+        }
+    };
+}
+
+quantity!(Voltage, "V");
+scale!(Voltage, from_volts, volts, 1.0);
+scale!(Voltage, from_millivolts, millivolts, 1.0e-3);
+
+quantity!(Current, "A");
+scale!(Current, from_amps, amps, 1.0);
+scale!(Current, from_microamps, microamps, 1.0e-6);
+scale!(Current, from_nanoamps, nanoamps, 1.0e-9);
+scale!(Current, from_picoamps, picoamps, 1.0e-12);
+
+quantity!(Conductance, "S");
+scale!(Conductance, from_siemens, siemens, 1.0);
+scale!(Conductance, from_millisiemens, millisiemens, 1.0e-3);
+scale!(Conductance, from_microsiemens, microsiemens, 1.0e-6);
+scale!(Conductance, from_nanosiemens, nanosiemens, 1.0e-9);
+
+quantity!(Time, "s");
+scale!(Time, from_seconds, seconds, 1.0);
+scale!(Time, from_millis, millis, 1.0e-3);
+
+quantity!(Capacitance, "F");
+scale!(Capacitance, from_farads, farads, 1.0);
+scale!(Capacitance, from_microfarads, microfarads, 1.0e-6);
+scale!(Capacitance, from_nanofarads, nanofarads, 1.0e-9);
+scale!(Capacitance, from_picofarads, picofarads, 1.0e-12);
+
+/// Ohm's law, `I = g * V`.
+impl Mul<Voltage> for Conductance {
+    type Output = Current;
+    fn mul(self, rhs: Voltage) -> Current {
+        Current(self.0 * rhs.0)
+    }
+}
+
+impl Mul<Conductance> for Voltage {
+    type Output = Current;
+    fn mul(self, rhs: Conductance) -> Current {
+        Current(self.0 * rhs.0)
+    }
+}
+
+/// Potential that drives a current through a conductance, `V = I / g`.
+impl Div<Conductance> for Current {
+    type Output = Voltage;
+    fn div(self, rhs: Conductance) -> Voltage {
+        Voltage(self.0 / rhs.0)
+    }
+}
+
+/// Time constant of a capacitance discharging through a conductance, `tau = C / g`.
+impl Div<Conductance> for Capacitance {
+    type Output = Time;
+    fn div(self, rhs: Conductance) -> Time {
+        Time(self.0 / rhs.0)
+    }
+}
+
+/// Charge delivered to a capacitance over a time step, `dV = I * dt / C`.
+impl Div<Capacitance> for Current {
+    type Output = VoltageRate;
+    fn div(self, rhs: Capacitance) -> VoltageRate {
+        VoltageRate(self.0 / rhs.0)
+    }
+}
+
+/// Rate of change of a voltage, in V/s.
+#[derive(Debug, Default, PartialEq, PartialOrd, Copy, Clone)]
+pub struct VoltageRate(f64);
+
+impl Mul<Time> for VoltageRate {
+    type Output = Voltage;
+    fn mul(self, rhs: Time) -> Voltage {
+        Voltage(self.0 * rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_round_trip() {
+        assert_eq!(Voltage::from_millivolts(-65.0).volts(), -0.065);
+        assert!((Current::from_nanoamps(0.3).picoamps() - 300.0).abs() < 1.0e-9);
+        assert!((Time::from_millis(250.0).seconds() - 0.25).abs() < 1.0e-15);
+        assert!((Capacitance::from_nanofarads(0.2).picofarads() - 200.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn quantities_combine() {
+        // 10 nS across 20 mV drives 200 pA
+        let i = Conductance::from_nanosiemens(10.0) * Voltage::from_millivolts(20.0);
+        assert!((i.picoamps() - 200.0).abs() < 1.0e-9);
+        // which charges 100 pF by 2 mV in 1 ms
+        let dv = i / Capacitance::from_picofarads(100.0) * Time::from_millis(1.0);
+        assert!((dv.millivolts() - 2.0).abs() < 1.0e-9);
+        assert!(((i / Conductance::from_nanosiemens(10.0)).millivolts() - 20.0).abs() < 1.0e-9);
+        // 100 pF through 10 nS discharges in 10 ms
+        let tau = Capacitance::from_picofarads(100.0) / Conductance::from_nanosiemens(10.0);
+        assert!((tau.millis() - 10.0).abs() < 1.0e-9);
+        assert_eq!(Time::from_millis(3.0) / Time::from_millis(1.5), 2.0);
+        let total: Current = [1.0, 2.0].iter().map(|&x| Current::from_picoamps(x)).sum();
+        assert!((total.picoamps() - 3.0).abs() < 1.0e-12);
+    }
+}